@@ -0,0 +1,114 @@
+use nvml_wrapper::{enums::device::GpuLockedClocksSetting, error::NvmlError, Device};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::Sets;
+
+/// A group of fields of `Sets` that is applied with one NVML call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Setting {
+    FreqOffset,
+    MemOffset,
+    PowerLimit,
+    LockedClocks,
+    LockedMemClocks,
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Setting::FreqOffset => "GPU frequency offset",
+            Setting::MemOffset => "GPU memory frequency offset",
+            Setting::PowerLimit => "GPU power limit",
+            Setting::LockedClocks => "GPU min and max clocks",
+            Setting::LockedMemClocks => "GPU min and max memory clocks",
+        })
+    }
+}
+
+/// The result of applying one setting, as printed by `zelos set --json`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Outcome {
+    pub setting: Setting,
+    /// The requested value, with its unit
+    pub value: String,
+    /// Error returned by NVML, if the setting was rejected
+    pub error: Option<String>,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            None => write!(f, "{} set to {}", self.setting, self.value),
+            Some(e) => write!(f, "Failed to set {} to {}: {}", self.setting, self.value, e),
+        }
+    }
+}
+
+/// How setting `setting` to `value` went, given NVML's `result`.
+fn outcome(setting: Setting, value: String, result: Result<(), NvmlError>) -> Outcome {
+    Outcome { setting, value, error: result.err().map(|e| format!("{:?}", e)) }
+}
+
+/// Applies every configured setting of `sets`, carrying on after a failure,
+/// and reports how each one went. Settings that aren't configured are left
+/// out.
+pub fn apply_each(sets: &Sets, device: &mut Device) -> Vec<Outcome> {
+    let mut outcomes = Vec::new();
+
+    if let Some(freq_offset) = sets.freq_offset {
+        outcomes.push(outcome(Setting::FreqOffset, format!("{} MHz", freq_offset), device.set_gpc_clock_vf_offset(freq_offset)));
+    }
+    if let Some(mem_offset) = sets.mem_offset {
+        outcomes.push(outcome(Setting::MemOffset, format!("{} MHz", mem_offset), device.set_mem_clock_vf_offset(mem_offset)));
+    }
+    if let Some(limit) = sets.power_limit {
+        outcomes.push(outcome(Setting::PowerLimit, format!("{} mW", limit), device.set_power_management_limit(limit)));
+    }
+    if let (Some(min_clock), Some(max_clock)) = (sets.min_clock, sets.max_clock) {
+        outcomes.push(outcome(
+            Setting::LockedClocks,
+            format!("{}-{} MHz", min_clock, max_clock),
+            device.set_gpu_locked_clocks(GpuLockedClocksSetting::Numeric { min_clock_mhz: min_clock, max_clock_mhz: max_clock }),
+        ));
+    }
+    if let (Some(min_mem_clock), Some(max_mem_clock)) = (sets.min_mem_clock, sets.max_mem_clock) {
+        outcomes.push(outcome(
+            Setting::LockedMemClocks,
+            format!("{}-{} MHz", min_mem_clock, max_mem_clock),
+            device.set_mem_locked_clocks(min_mem_clock, max_mem_clock),
+        ));
+    }
+
+    outcomes
+}
+
+/// Puts every setting that `sets` leaves out back to the driver default, so
+/// applying `sets` afterwards gives the whole state it describes rather than
+/// only changing what it configures.
+pub fn reset_unset(sets: &Sets, device: &mut Device) -> Vec<Outcome> {
+    let mut outcomes = Vec::new();
+
+    if sets.freq_offset.is_none() {
+        outcomes.push(outcome(Setting::FreqOffset, "0 MHz".to_string(), device.set_gpc_clock_vf_offset(0)));
+    }
+    if sets.mem_offset.is_none() {
+        outcomes.push(outcome(Setting::MemOffset, "0 MHz".to_string(), device.set_mem_clock_vf_offset(0)));
+    }
+    if sets.power_limit.is_none() {
+        outcomes.push(match device.power_management_limit_default() {
+            Ok(limit) => outcome(Setting::PowerLimit, format!("{} mW (default)", limit), device.set_power_management_limit(limit)),
+            Err(e) => outcome(Setting::PowerLimit, "the default".to_string(), Err(e)),
+        });
+    }
+    if sets.min_clock.is_none() || sets.max_clock.is_none() {
+        outcomes.push(outcome(Setting::LockedClocks, "unlocked".to_string(), device.reset_gpu_locked_clocks()));
+    }
+    if sets.min_mem_clock.is_none() || sets.max_mem_clock.is_none() {
+        outcomes.push(outcome(Setting::LockedMemClocks, "unlocked".to_string(), device.reset_mem_locked_clocks()));
+    }
+
+    outcomes
+}
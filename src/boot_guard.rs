@@ -0,0 +1,307 @@
+use nvml_wrapper::Device;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Kernel command line flag that skips applying anything at boot.
+pub const SAFE_FLAG: &str = "zelos.safe=1";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BootGuardConfig {
+    /// Unclean boots in a row after which defaults are applied instead
+    pub max_unclean_boots: u32,
+    /// Seconds of uptime after which a boot counts as clean
+    pub stable_secs: u64,
+    /// Where the boot counter is persisted
+    pub state_file: String,
+}
+
+impl Default for BootGuardConfig {
+    fn default() -> Self {
+        Self {
+            max_unclean_boots: 3,
+            stable_secs: 120,
+            state_file: "/var/lib/zelos/boot-guard.json".to_string(),
+        }
+    }
+}
+
+impl BootGuardConfig {
+    /// Reads the `bootGuard` section of the config file, falling back to the
+    /// defaults if the file is missing, e.g. for a job that runs `set`.
+    pub fn load(config_path: &str) -> Self {
+        crate::config_section(config_path, "bootGuard")
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootGuardState {
+    /// Boots that applied the profile but never reached the stable uptime
+    pub unclean_boots: u32,
+    /// When the guard switched to defaults, in seconds since the Unix epoch.
+    /// Stays set until `zelos boot-guard reset`.
+    pub tripped_at: Option<u64>,
+}
+
+impl BootGuardState {
+    pub fn load(path: &str) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    /// Writes the state and flushes it to disk, so the counter survives a
+    /// crash right after the profile is applied.
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()
+    }
+
+    /// Human readable explanation of a tripped guard, if it is tripped.
+    pub fn tripped_message(&self) -> Option<String> {
+        let at = self.tripped_at?;
+        let time = humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(at));
+        Some(format!(
+            "Boot-loop protection tripped at {} after {} unclean boots in a row: defaults are applied at boot instead of your settings. Run `zelos boot-guard reset` once they are fixed.",
+            time, self.unclean_boots
+        ))
+    }
+}
+
+/// What a boot-time apply should do.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Apply the profile as usual.
+    Apply,
+    /// Apply the driver defaults instead of the profile.
+    Defaults,
+    /// Don't touch the GPU at all.
+    Skip,
+}
+
+/// The kernel command line of the running system.
+pub fn kernel_cmdline() -> String {
+    std::fs::read_to_string("/proc/cmdline").unwrap_or_default()
+}
+
+fn safe_mode(cmdline: &str) -> bool {
+    cmdline.split_whitespace().any(|arg| arg == SAFE_FLAG)
+}
+
+/// Decides whether anything besides the boot job, like `zelos reapply` or
+/// the daemon, may apply the settings, given the kernel `cmdline`. Unlike
+/// [`begin`] it doesn't count the boot. A guard that will trip at the next
+/// boot job already counts as tripped, so a reapply that races the boot job
+/// can't apply the settings that keep crashing.
+pub fn check(config: &BootGuardConfig, cmdline: &str) -> Verdict {
+    if safe_mode(cmdline) {
+        return Verdict::Skip;
+    }
+    let state = BootGuardState::load(&config.state_file);
+    if state.tripped_at.is_some() || state.unclean_boots >= config.max_unclean_boots {
+        return Verdict::Defaults;
+    }
+    Verdict::Apply
+}
+
+/// Decides what to do at boot given the kernel `cmdline`, counting this
+/// boot as unclean until [`wait_until_stable`] clears it.
+pub fn begin(config: &BootGuardConfig, cmdline: &str) -> Verdict {
+    if safe_mode(cmdline) {
+        eprintln!("{} is set on the kernel command line, not applying any settings.", SAFE_FLAG);
+        return Verdict::Skip;
+    }
+
+    let mut state = BootGuardState::load(&config.state_file);
+    if state.tripped_at.is_none() && state.unclean_boots >= config.max_unclean_boots {
+        state.tripped_at = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs());
+        if let Err(e) = state.save(&config.state_file) {
+            eprintln!("Failed to save boot guard state to {}: {}", config.state_file, e);
+        }
+    }
+    if let Some(message) = state.tripped_message() {
+        eprintln!("{}", message);
+        return Verdict::Defaults;
+    }
+
+    state.unclean_boots += 1;
+    if let Err(e) = state.save(&config.state_file) {
+        eprintln!("Failed to save boot guard state to {}: {}", config.state_file, e);
+    }
+    Verdict::Apply
+}
+
+fn uptime() -> Option<Duration> {
+    let s = std::fs::read_to_string("/proc/uptime").ok()?;
+    let secs: f64 = s.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_secs_f64(secs))
+}
+
+/// Blocks until the system has been up for the stable uptime, then clears
+/// the unclean boot counter.
+pub fn wait_until_stable(config: &BootGuardConfig) {
+    let stable = Duration::from_secs(config.stable_secs);
+    if let Some(up) = uptime() {
+        if up < stable {
+            std::thread::sleep(stable - up);
+        }
+    }
+
+    match clear_counter(&config.state_file) {
+        Ok(()) => println!("System stable for {}s, cleared the unclean boot counter.", config.stable_secs),
+        Err(e) => eprintln!("Failed to save boot guard state to {}: {}", config.state_file, e),
+    }
+}
+
+/// Counts the current boot as clean. A tripped guard stays tripped.
+fn clear_counter(state_file: &str) -> std::io::Result<()> {
+    let mut state = BootGuardState::load(state_file);
+    state.unclean_boots = 0;
+    state.save(state_file)
+}
+
+/// Warns on stderr if the guard has tripped, so the settings not being
+/// applied at boot doesn't go unnoticed by CLI users.
+pub fn warn_if_tripped(config_path: &str) {
+    let config = BootGuardConfig::load(config_path);
+    if let Some(message) = BootGuardState::load(&config.state_file).tripped_message() {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+/// Clears the unclean boot counter once the system is stable. OpenRC waits
+/// for the job to return before carrying on with boot, so there the wait
+/// happens in a detached child instead.
+pub fn finish(config: &BootGuardConfig, config_path: &str) {
+    if std::env::var_os("RC_SVCNAME").is_none() {
+        wait_until_stable(config);
+        return;
+    }
+
+    let spawned = std::env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .arg("--file")
+            .arg(config_path)
+            .args(["boot-guard", "wait"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    });
+    if let Err(e) = spawned {
+        eprintln!("Failed to spawn the boot guard waiter: {}", e);
+    }
+}
+
+/// Puts `device` back to the driver defaults, logging anything that fails.
+pub fn reset_device(index: u32, device: &mut Device) {
+    if let Err(e) = device.set_gpc_clock_vf_offset(0) {
+        eprintln!("GPU {}: failed to reset the core clock offset: {:?}", index, e);
+    }
+    if let Err(e) = device.set_mem_clock_vf_offset(0) {
+        eprintln!("GPU {}: failed to reset the memory clock offset: {:?}", index, e);
+    }
+    match device.power_management_limit_default() {
+        Ok(limit) => {
+            if let Err(e) = device.set_power_management_limit(limit) {
+                eprintln!("GPU {}: failed to reset the power limit: {:?}", index, e);
+            }
+        }
+        Err(e) => eprintln!("GPU {}: failed to get the default power limit: {:?}", index, e),
+    }
+    if let Err(e) = device.reset_gpu_locked_clocks() {
+        eprintln!("GPU {}: failed to reset the locked clocks: {:?}", index, e);
+    }
+    if let Err(e) = device.reset_mem_locked_clocks() {
+        eprintln!("GPU {}: failed to reset the locked memory clocks: {:?}", index, e);
+    }
+    println!("GPU {}: applied driver defaults.", index);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(dir: &tempfile::TempDir) -> BootGuardConfig {
+        BootGuardConfig {
+            max_unclean_boots: 2,
+            stable_secs: 0,
+            state_file: dir.path().join("boot-guard.json").display().to_string(),
+        }
+    }
+
+    const CMDLINE: &str = "BOOT_IMAGE=/vmlinuz root=/dev/sda1 quiet";
+
+    #[test]
+    fn counts_unclean_boots_until_tripped() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(&dir);
+        assert_eq!(begin(&config, CMDLINE), Verdict::Apply);
+        assert_eq!(BootGuardState::load(&config.state_file).unclean_boots, 1);
+        assert_eq!(begin(&config, CMDLINE), Verdict::Apply);
+        assert_eq!(BootGuardState::load(&config.state_file).unclean_boots, 2);
+
+        assert_eq!(begin(&config, CMDLINE), Verdict::Defaults);
+        let state = BootGuardState::load(&config.state_file);
+        assert_eq!(state.unclean_boots, 2);
+        assert!(state.tripped_at.is_some());
+        assert!(state.tripped_message().is_some());
+
+        // Stays tripped, even after a clean boot, until reset.
+        clear_counter(&config.state_file).unwrap();
+        assert_eq!(begin(&config, CMDLINE), Verdict::Defaults);
+        BootGuardState::default().save(&config.state_file).unwrap();
+        assert_eq!(begin(&config, CMDLINE), Verdict::Apply);
+    }
+
+    #[test]
+    fn clean_boot_clears_the_counter() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(&dir);
+        begin(&config, CMDLINE);
+        begin(&config, CMDLINE);
+        wait_until_stable(&config);
+        assert_eq!(BootGuardState::load(&config.state_file).unclean_boots, 0);
+        assert_eq!(begin(&config, CMDLINE), Verdict::Apply);
+        assert_eq!(BootGuardState::load(&config.state_file).unclean_boots, 1);
+    }
+
+    #[test]
+    fn check_follows_the_guard_without_counting() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(&dir);
+        assert_eq!(check(&config, CMDLINE), Verdict::Apply);
+        assert_eq!(check(&config, "quiet zelos.safe=1"), Verdict::Skip);
+        assert_eq!(BootGuardState::load(&config.state_file).unclean_boots, 0);
+
+        begin(&config, CMDLINE);
+        assert_eq!(check(&config, CMDLINE), Verdict::Apply);
+        // The next boot job would trip.
+        begin(&config, CMDLINE);
+        assert_eq!(check(&config, CMDLINE), Verdict::Defaults);
+        assert_eq!(begin(&config, CMDLINE), Verdict::Defaults);
+        assert_eq!(check(&config, CMDLINE), Verdict::Defaults);
+        assert_eq!(check(&config, "zelos.safe=1"), Verdict::Skip);
+
+        BootGuardState::default().save(&config.state_file).unwrap();
+        assert_eq!(check(&config, CMDLINE), Verdict::Apply);
+    }
+
+    #[test]
+    fn safe_flag_skips_without_counting() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(&dir);
+        assert_eq!(begin(&config, "root=/dev/sda1 zelos.safe=1 quiet"), Verdict::Skip);
+        assert_eq!(BootGuardState::load(&config.state_file).unclean_boots, 0);
+        // Only the exact flag counts.
+        assert_eq!(begin(&config, "root=/dev/sda1 zelos.safe=0 xzelos.safe=1"), Verdict::Apply);
+    }
+}
@@ -0,0 +1,388 @@
+use nvml_wrapper::Nvml;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::boot_guard::{self, BootGuardConfig, Verdict};
+use crate::diff::{self, Field, FieldDiff};
+use crate::{apply, fan, Config, Sets};
+
+/// Drift events kept in the history file; older ones are dropped.
+const HISTORY_LEN: usize = 500;
+
+/// What the daemon does when a field no longer matches the config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Policy {
+    /// Only log the drift.
+    #[default]
+    Warn,
+    /// Log the drift and set the field back to its configured value.
+    Enforce,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DaemonConfig {
+    /// Seconds between two checks of the live device state
+    pub interval_secs: u64,
+    /// Seconds between two fan speed updates while a custom fan curve is on
+    pub fan_interval_secs: u64,
+    /// Per-field policy; fields that aren't listed are only warned about
+    pub policy: HashMap<Field, Policy>,
+    /// Where the drift history is persisted
+    pub history_file: String,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            fan_interval_secs: 2,
+            policy: HashMap::new(),
+            history_file: "/var/lib/zelos/drift.json".to_string(),
+        }
+    }
+}
+
+impl DaemonConfig {
+    fn policy(&self, field: Field) -> Policy {
+        self.policy.get(&field).copied().unwrap_or_default()
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+
+    fn fan_interval(&self) -> Duration {
+        Duration::from_secs(self.fan_interval_secs.max(1))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftEvent {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub index: u32,
+    pub field: Field,
+    pub expected: i64,
+    pub actual: i64,
+    pub policy: Policy,
+    /// Error returned by NVML if enforcing the field failed
+    pub error: Option<String>,
+}
+
+impl std::fmt::Display for DriftEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(self.timestamp));
+        let diff = FieldDiff { field: self.field, expected: self.expected, actual: self.actual };
+        write!(f, "{} GPU {}: {}", time, self.index, diff)?;
+        match (self.policy, &self.error) {
+            (Policy::Warn, _) => write!(f, " (warned)"),
+            (Policy::Enforce, None) => write!(f, " (enforced)"),
+            (Policy::Enforce, Some(e)) => write!(f, " (failed to enforce: {})", e),
+        }
+    }
+}
+
+pub fn load_history(path: &str) -> VecDeque<DriftEvent> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(path: &str, history: &VecDeque<DriftEvent>) -> std::io::Result<()> {
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(history)?)
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn reload(path: &str) -> Result<Config, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// The fields of GPU `index` in `diffs` that weren't drifting at the last
+/// check, or drifted to another value, remembering the drifts in `seen`.
+/// Fields that match again are forgotten, so a later drift is new again.
+fn new_drifts(seen: &mut HashMap<(u32, Field), i64>, index: u32, diffs: &[FieldDiff]) -> HashSet<Field> {
+    seen.retain(|&(i, field), _| i != index || diffs.iter().any(|d| d.field == field));
+    diffs.iter().filter(|d| seen.insert((index, d.field), d.actual) != Some(d.actual)).map(|d| d.field).collect()
+}
+
+/// Compares every configured GPU to the config, warning about or enforcing
+/// each drifted field. A drift is logged when it starts or its value
+/// changes, not on every check. Returns whether a device couldn't be
+/// reached.
+fn check_drift(handle: &Nvml, config: &Config, daemon: &DaemonConfig, history: &mut VecDeque<DriftEvent>, seen: &mut HashMap<(u32, Field), i64>) -> bool {
+    let mut recorded = false;
+    let mut lost = false;
+    for (index, sets) in &config.sets {
+        let mut device = match handle.device_by_index(*index) {
+            Ok(device) => device,
+            Err(e) => {
+                eprintln!("GPU {}: failed to get device: {:?}", index, e);
+                lost = true;
+                continue;
+            }
+        };
+
+        let diffs = diff::diff(sets, &device);
+        let new = new_drifts(seen, *index, &diffs);
+        let mut restored = false;
+        for d in diffs {
+            let policy = daemon.policy(d.field);
+            let error = match policy {
+                Policy::Warn => None,
+                Policy::Enforce => {
+                    restored = true;
+                    d.restore(&mut device).err().map(|e| format!("{:?}", e))
+                }
+            };
+            if !new.contains(&d.field) {
+                continue;
+            }
+            let event = DriftEvent {
+                timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+                index: *index,
+                field: d.field,
+                expected: d.expected,
+                actual: d.actual,
+                policy,
+                error,
+            };
+            println!("{}", event);
+            history.push_back(event);
+            recorded = true;
+        }
+
+        // Locks can't be read back, so whatever reset the other fields
+        // probably reset them too.
+        if restored && sets.locks_clocks() {
+            let locks = Sets { freq_offset: None, mem_offset: None, power_limit: None, ..sets.clone() };
+            for outcome in apply::apply_each(&locks, &mut device) {
+                if outcome.error.is_some() {
+                    eprintln!("GPU {}: {}", index, outcome);
+                }
+            }
+        }
+    }
+
+    if recorded {
+        while history.len() > HISTORY_LEN {
+            history.pop_front();
+        }
+        if let Err(e) = save_history(&daemon.history_file, history) {
+            eprintln!("Failed to save drift history to {}: {}", daemon.history_file, e);
+        }
+    }
+    lost
+}
+
+/// The GPUs whose fans the daemon controls. Their fans are handed back to
+/// the driver when it's dropped, so stopping the daemon, or a panic, doesn't
+/// leave them at the last speed the curve set.
+#[derive(Default)]
+struct DrivenFans(HashSet<u32>);
+
+impl Drop for DrivenFans {
+    fn drop(&mut self) {
+        if self.0.is_empty() {
+            return;
+        }
+        let nvml = match Nvml::init() {
+            Ok(nvml) => nvml,
+            Err(e) => {
+                eprintln!("Failed to initialize NVML to restore automatic fan control: {:?}", e);
+                return;
+            }
+        };
+        for index in self.0.drain() {
+            match nvml.device_by_index(index).and_then(|mut device| fan::restore_auto(&mut device)) {
+                Ok(()) => println!("GPU {}: fans back under driver control.", index),
+                Err(e) => eprintln!("GPU {}: failed to restore automatic fan control: {:?}", index, e),
+            }
+        }
+    }
+}
+
+/// Sets the fans of every GPU with a custom curve, and hands the fans of
+/// GPUs whose curve was switched off back to the driver. `driven` holds the
+/// GPUs whose fans zelos controls. Returns whether a device couldn't be
+/// reached.
+fn drive_fans(handle: &Nvml, config: &Config, driven: &mut HashSet<u32>) -> bool {
+    let mut lost = false;
+    let released: Vec<u32> = driven.iter().copied().filter(|i| !config.fan_curves.get(i).is_some_and(|c| c.custom)).collect();
+    for index in released {
+        driven.remove(&index);
+        match handle.device_by_index(index) {
+            Ok(mut device) => match fan::restore_auto(&mut device) {
+                Ok(()) => println!("GPU {}: fans back under driver control.", index),
+                Err(e) => eprintln!("GPU {}: failed to restore automatic fan control: {:?}", index, e),
+            },
+            Err(e) => {
+                eprintln!("GPU {}: failed to get device: {:?}", index, e);
+                lost = true;
+            }
+        }
+    }
+
+    for (index, curve) in config.fan_curves.iter().filter(|(_, c)| c.custom) {
+        let mut device = match handle.device_by_index(*index) {
+            Ok(device) => device,
+            Err(e) => {
+                eprintln!("GPU {}: failed to get device: {:?}", index, e);
+                lost = true;
+                continue;
+            }
+        };
+        match fan::apply(&mut device, curve) {
+            Ok(_) => {
+                if driven.insert(*index) {
+                    println!("GPU {}: fans following the custom curve.", index);
+                }
+            }
+            Err(e) => eprintln!("GPU {}: failed to set fan speed: {:?}", index, e),
+        }
+    }
+    lost
+}
+
+/// Checks every configured GPU against the config forever, warning about or
+/// enforcing each drifted field according to its policy, and drives the
+/// fans of GPUs with a custom fan curve. The config file is reloaded when it
+/// changes. Returns on SIGTERM or SIGINT, after handing the fans back to
+/// the driver.
+pub fn run(config_path: &str) {
+    let mut daemon: DaemonConfig = crate::config_section(config_path, "daemon");
+    let mut config = Config::load(config_path);
+    let mut config_modified = modified(config_path);
+    let mut guard = BootGuardConfig::load(config_path);
+    let cmdline = boot_guard::kernel_cmdline();
+    let mut guarded = false;
+    let mut history = load_history(&daemon.history_file);
+    let mut seen = HashMap::new();
+    let mut nvml: Option<Nvml> = None;
+    let mut driven = DrivenFans::default();
+    let mut next_check = Instant::now();
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT] {
+        if let Err(e) = signal_hook::flag::register(signal, stop.clone()) {
+            eprintln!("Failed to handle signal {}: {}", signal, e);
+        }
+    }
+
+    println!("Watching {} GPU(s) every {}s for drift.", config.sets.len(), daemon.interval().as_secs());
+
+    while !stop.load(Ordering::Relaxed) {
+        // Pick up changes saved by the GUI or `zelos profile apply`.
+        let now_modified = modified(config_path);
+        if now_modified != config_modified {
+            config_modified = now_modified;
+            match reload(config_path) {
+                Ok(new_config) => {
+                    config = new_config;
+                    let new_daemon: DaemonConfig = crate::config_section(config_path, "daemon");
+                    if new_daemon.history_file != daemon.history_file {
+                        history = load_history(&new_daemon.history_file);
+                    }
+                    daemon = new_daemon;
+                    guard = BootGuardConfig::load(config_path);
+                    println!("Reloaded {}.", config_path);
+                }
+                Err(e) => eprintln!("Failed to reload {}: {}", config_path, e),
+            }
+        }
+
+        let check_due = Instant::now() >= next_check;
+        if check_due {
+            next_check = Instant::now() + daemon.interval();
+        }
+
+        // NVML handles go stale across a driver reload, so drop ours on
+        // error and initialize a fresh one on the next pass.
+        if nvml.is_none() {
+            nvml = Nvml::init().map_err(|e| eprintln!("Failed to initialize NVML: {:?}", e)).ok();
+        }
+
+        if let Some(handle) = &nvml {
+            let mut lost = false;
+            if !config.fan_curves.is_empty() || !driven.0.is_empty() {
+                lost |= drive_fans(handle, &config, &mut driven.0);
+            }
+            if check_due {
+                // Checked every time so `zelos boot-guard reset` resumes
+                // enforcing without a restart.
+                let allowed = boot_guard::check(&guard, &cmdline) == Verdict::Apply;
+                if allowed == guarded {
+                    guarded = !allowed;
+                    if guarded {
+                        println!("Boot guard tripped or safe mode, not enforcing settings.");
+                    } else {
+                        println!("Boot guard cleared, enforcing settings again.");
+                    }
+                }
+                if allowed {
+                    lost |= check_drift(handle, &config, &daemon, &mut history, &mut seen);
+                }
+            }
+            if lost {
+                nvml = None;
+            }
+        }
+
+        let tick = if driven.0.is_empty() && !config.fan_curves.values().any(|c| c.custom) { daemon.interval() } else { daemon.fan_interval() };
+        // Sleep in short steps to notice a signal quickly.
+        let wake = Instant::now() + tick.min(next_check.saturating_duration_since(Instant::now()));
+        while !stop.load(Ordering::Relaxed) && Instant::now() < wake {
+            std::thread::sleep(wake.saturating_duration_since(Instant::now()).min(Duration::from_millis(200)));
+        }
+    }
+    println!("Stopping.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drift(field: Field, actual: i64) -> FieldDiff {
+        FieldDiff { field, expected: 0, actual }
+    }
+
+    #[test]
+    fn persisting_drift_is_reported_once() {
+        let mut seen = HashMap::new();
+        let diffs = [drift(Field::FreqOffset, 100), drift(Field::MemOffset, 200)];
+        assert_eq!(new_drifts(&mut seen, 0, &diffs), HashSet::from([Field::FreqOffset, Field::MemOffset]));
+        assert!(new_drifts(&mut seen, 0, &diffs).is_empty());
+        // The same drift on another GPU is its own.
+        assert_eq!(new_drifts(&mut seen, 1, &diffs[..1]), HashSet::from([Field::FreqOffset]));
+    }
+
+    #[test]
+    fn changed_value_is_reported_again() {
+        let mut seen = HashMap::new();
+        new_drifts(&mut seen, 0, &[drift(Field::PowerLimit, 150_000)]);
+        assert_eq!(new_drifts(&mut seen, 0, &[drift(Field::PowerLimit, 120_000)]), HashSet::from([Field::PowerLimit]));
+    }
+
+    #[test]
+    fn drift_after_matching_again_is_new() {
+        let mut seen = HashMap::new();
+        new_drifts(&mut seen, 0, &[drift(Field::FreqOffset, 100)]);
+        new_drifts(&mut seen, 1, &[drift(Field::FreqOffset, 100)]);
+        assert!(new_drifts(&mut seen, 0, &[]).is_empty());
+        // GPU 1 is untouched by GPU 0 matching again.
+        assert!(new_drifts(&mut seen, 1, &[drift(Field::FreqOffset, 100)]).is_empty());
+        assert_eq!(new_drifts(&mut seen, 0, &[drift(Field::FreqOffset, 100)]), HashSet::from([Field::FreqOffset]));
+    }
+}
@@ -0,0 +1,144 @@
+use nvml_wrapper::{error::NvmlError, Device};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::Sets;
+
+/// A setting that can be read back from the device and compared to the config.
+///
+/// Locked clocks are applied but not listed here: NVML offers no way to read
+/// them back, so `zelos reapply` always applies them and the daemon reapplies
+/// them whenever it restores another field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Field {
+    FreqOffset,
+    MemOffset,
+    PowerLimit,
+}
+
+impl Field {
+    pub fn unit(self) -> &'static str {
+        match self {
+            Field::FreqOffset | Field::MemOffset => "MHz",
+            Field::PowerLimit => "mW",
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Field::FreqOffset => "core clock offset",
+            Field::MemOffset => "memory clock offset",
+            Field::PowerLimit => "power limit",
+        })
+    }
+}
+
+/// A configured value that doesn't match the live device state.
+#[derive(Clone, Debug)]
+pub struct FieldDiff {
+    pub field: Field,
+    pub expected: i64,
+    pub actual: i64,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = self.field.unit();
+        write!(f, "{} is {} {}, expected {} {}", self.field, self.actual, unit, self.expected, unit)
+    }
+}
+
+impl FieldDiff {
+    /// Sets the field back to its configured value.
+    pub fn restore(&self, device: &mut Device) -> Result<(), NvmlError> {
+        match self.field {
+            Field::FreqOffset => device.set_gpc_clock_vf_offset(self.expected as i32),
+            Field::MemOffset => device.set_mem_clock_vf_offset(self.expected as i32),
+            Field::PowerLimit => device.set_power_management_limit(self.expected as u32),
+        }
+    }
+}
+
+/// Compares the configured `sets` to the live state of `device`.
+///
+/// Fields that are not configured, or that the device can't report, are
+/// skipped.
+pub fn diff(sets: &Sets, device: &Device) -> Vec<FieldDiff> {
+    compare(sets, |field| match field {
+        Field::FreqOffset => device.gpc_clock_vf_offset().ok().map(i64::from),
+        Field::MemOffset => device.mem_clock_vf_offset().ok().map(i64::from),
+        Field::PowerLimit => device.power_management_limit().ok().map(i64::from),
+    })
+}
+
+/// Compares the configured `sets` to the live values `read` reports.
+fn compare(sets: &Sets, read: impl Fn(Field) -> Option<i64>) -> Vec<FieldDiff> {
+    let configured = [
+        (Field::FreqOffset, sets.freq_offset.map(i64::from)),
+        (Field::MemOffset, sets.mem_offset.map(i64::from)),
+        (Field::PowerLimit, sets.power_limit.map(i64::from)),
+    ];
+    configured
+        .into_iter()
+        .filter_map(|(field, expected)| {
+            let expected = expected?;
+            let actual = read(field)?;
+            (expected != actual).then_some(FieldDiff { field, expected, actual })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sets() -> Sets {
+        Sets { freq_offset: Some(150), mem_offset: Some(-500), power_limit: Some(200_000), ..Default::default() }
+    }
+
+    #[test]
+    fn matching_state_has_no_diffs() {
+        let live = |field| match field {
+            Field::FreqOffset => Some(150),
+            Field::MemOffset => Some(-500),
+            Field::PowerLimit => Some(200_000),
+        };
+        assert!(compare(&sets(), live).is_empty());
+    }
+
+    #[test]
+    fn reports_drifted_fields() {
+        let live = |field| match field {
+            Field::FreqOffset => Some(0),
+            Field::MemOffset => Some(-500),
+            Field::PowerLimit => Some(250_000),
+        };
+        let diffs = compare(&sets(), live);
+        let found: Vec<_> = diffs.iter().map(|d| (d.field, d.expected, d.actual)).collect();
+        assert_eq!(found, [(Field::FreqOffset, 150, 0), (Field::PowerLimit, 200_000, 250_000)]);
+    }
+
+    #[test]
+    fn skips_unconfigured_and_unreadable_fields() {
+        let sets = Sets { freq_offset: Some(150), power_limit: Some(200_000), ..Default::default() };
+        let live = |field| match field {
+            Field::FreqOffset => None,
+            Field::MemOffset => Some(1000),
+            Field::PowerLimit => Some(100_000),
+        };
+        let diffs = compare(&sets, live);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].field, Field::PowerLimit);
+    }
+
+    #[test]
+    fn field_diff_display() {
+        let d = FieldDiff { field: Field::MemOffset, expected: -500, actual: 0 };
+        assert_eq!(d.to_string(), "memory clock offset is 0 MHz, expected -500 MHz");
+        let d = FieldDiff { field: Field::PowerLimit, expected: 200_000, actual: 250_000 };
+        assert_eq!(d.to_string(), "power limit is 250000 mW, expected 200000 mW");
+    }
+}
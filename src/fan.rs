@@ -0,0 +1,107 @@
+use nvml_wrapper::{enum_wrappers::device::TemperatureSensor, error::NvmlError, Device};
+use serde::{Deserialize, Serialize};
+
+/// A point of a fan curve: fan speed in percent at a GPU temperature in °C.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct FanPoint {
+    pub temp: u32,
+    pub speed: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FanCurve {
+    /// Drive the fans from `points`; otherwise the driver controls them
+    pub custom: bool,
+    /// Sorted by temperature, though `speed_at` doesn't rely on it
+    pub points: Vec<FanPoint>,
+}
+
+impl FanCurve {
+    /// Fan speed for `temp`, interpolated linearly between the points and
+    /// held flat beyond the first and last one. `None` without points.
+    pub fn speed_at(&self, temp: u32) -> Option<u32> {
+        let mut points = self.points.clone();
+        points.sort_by_key(|p| p.temp);
+        let first = points.first()?;
+        let last = points.last()?;
+        if temp <= first.temp {
+            return Some(first.speed);
+        }
+        if temp >= last.temp {
+            return Some(last.speed);
+        }
+        let pair = points.windows(2).find(|w| temp <= w[1].temp)?;
+        let (a, b) = (pair[0], pair[1]);
+        if b.temp == a.temp {
+            return Some(b.speed);
+        }
+        let frac = (temp - a.temp) as f64 / (b.temp - a.temp) as f64;
+        Some((a.speed as f64 + (b.speed as f64 - a.speed as f64) * frac).round() as u32)
+    }
+}
+
+/// Sets every fan of `device` to the curve's speed for the current GPU
+/// temperature, returning that speed.
+pub fn apply(device: &mut Device, curve: &FanCurve) -> Result<Option<u32>, NvmlError> {
+    let temp = device.temperature(TemperatureSensor::Gpu)?;
+    let Some(speed) = curve.speed_at(temp) else {
+        return Ok(None);
+    };
+    for fan in 0..device.num_fans()? {
+        device.set_fan_speed(fan, speed)?;
+    }
+    Ok(Some(speed))
+}
+
+/// Hands every fan of `device` back to the driver.
+pub fn restore_auto(device: &mut Device) -> Result<(), NvmlError> {
+    for fan in 0..device.num_fans()? {
+        device.set_default_fan_speed(fan)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(points: &[(u32, u32)]) -> FanCurve {
+        FanCurve { custom: true, points: points.iter().map(|&(temp, speed)| FanPoint { temp, speed }).collect() }
+    }
+
+    #[test]
+    fn held_flat_beyond_the_ends() {
+        let c = curve(&[(40, 30), (80, 90)]);
+        assert_eq!(c.speed_at(0), Some(30));
+        assert_eq!(c.speed_at(40), Some(30));
+        assert_eq!(c.speed_at(80), Some(90));
+        assert_eq!(c.speed_at(105), Some(90));
+    }
+
+    #[test]
+    fn exact_points_and_interpolation() {
+        let c = curve(&[(30, 20), (50, 40), (70, 100)]);
+        assert_eq!(c.speed_at(50), Some(40));
+        assert_eq!(c.speed_at(40), Some(30));
+        assert_eq!(c.speed_at(60), Some(70));
+        // Rounded to the nearest percent.
+        assert_eq!(c.speed_at(55), Some(55));
+        assert_eq!(c.speed_at(33), Some(23));
+    }
+
+    #[test]
+    fn unsorted_points() {
+        let sorted = curve(&[(30, 20), (50, 40), (70, 100)]);
+        let unsorted = curve(&[(70, 100), (30, 20), (50, 40)]);
+        for temp in [0, 30, 40, 50, 60, 70, 90] {
+            assert_eq!(unsorted.speed_at(temp), sorted.speed_at(temp), "at {} °C", temp);
+        }
+    }
+
+    #[test]
+    fn no_points() {
+        assert_eq!(curve(&[]).speed_at(60), None);
+        assert_eq!(curve(&[(60, 50)]).speed_at(20), Some(50));
+    }
+}
@@ -1,5 +1,24 @@
 // Clean, redesigned GTK4 GUI
 
+#[cfg(feature = "gui")]
+mod activity;
+#[cfg(feature = "gui")]
+mod chart;
+#[cfg(feature = "gui")]
+mod i18n;
+#[cfg(feature = "gui")]
+mod mini;
+#[cfg(feature = "gui")]
+mod notify;
+#[cfg(feature = "gui")]
+mod prefs;
+#[cfg(feature = "gui")]
+mod sampler;
+#[cfg(feature = "gui")]
+mod tray;
+#[cfg(feature = "gui")]
+mod undo;
+
 #[cfg(feature = "gui")]
 pub mod imp {
     use libadwaita as adw;
@@ -9,9 +28,447 @@ pub mod imp {
     use gtk4::cairo;
     use gtk4::glib;
 
-    fn build_command(gpu_index: &str, power: i32, freq: i32, mem: i32, min_clock: i32, max_clock: i32) -> String {
-        let prog = std::env::current_exe().map(|p| p.display().to_string()).unwrap_or_else(|_| "zelos".to_string());
-        format!("{} set --index {} --power-limit {} --freq-offset {} --mem-offset {} --min-clock {} --max-clock {}", prog, gpu_index, power, freq, mem, min_clock, max_clock)
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use super::activity::ActivityLog;
+    use super::i18n::{self, number, tr};
+    use super::chart::{draw_sparkline, ChartView, EventKind, GpuHistory, GpuIdentity, Histories, Metric, TimeChart, SPARKLINE_SECS};
+    use super::mini::MiniMonitor;
+    use super::notify::Notifier;
+    use super::prefs::{self, Preferences};
+    use super::sampler::{self, GpuSample, LiveSettings};
+    use super::tray::{self, ItemKind, MenuItem, TrayAction};
+    use super::undo::UndoHistory;
+    use crate::apply::Outcome;
+    use crate::fan::{FanCurve, FanPoint};
+    use crate::{Config, Sets};
+
+    /// What Apply Settings runs: `zelos set` for one GPU, elevated through
+    /// pkexec. The command preview only renders it.
+    struct ApplyRequest {
+        index: u32,
+        sets: Sets,
+        /// Reset whatever `sets` leaves out to the driver default, to put
+        /// the GPU back into a recorded state
+        reset_unset: bool,
+    }
+
+    impl ApplyRequest {
+        fn exe() -> String {
+            std::env::current_exe().map(|p| p.display().to_string()).unwrap_or_else(|_| "zelos".to_string())
+        }
+
+        fn args(&self) -> Vec<String> {
+            let mut args = vec!["set".to_string(), "--index".to_string(), self.index.to_string()];
+            let mut push = |flag: &str, value: Option<String>| {
+                if let Some(value) = value {
+                    args.push(flag.to_string());
+                    args.push(value);
+                }
+            };
+            push("--power-limit", self.sets.power_limit.map(|v| v.to_string()));
+            push("--freq-offset", self.sets.freq_offset.map(|v| v.to_string()));
+            push("--mem-offset", self.sets.mem_offset.map(|v| v.to_string()));
+            push("--min-clock", self.sets.min_clock.map(|v| v.to_string()));
+            push("--max-clock", self.sets.max_clock.map(|v| v.to_string()));
+            push("--min-mem-clock", self.sets.min_mem_clock.map(|v| v.to_string()));
+            push("--max-mem-clock", self.sets.max_mem_clock.map(|v| v.to_string()));
+            if self.reset_unset {
+                args.push("--reset-unset".to_string());
+            }
+            args
+        }
+
+        /// The power limit and offsets the GPU should report once the request
+        /// is applied. What `sets` leaves alone is `None`, unless it's reset
+        /// to the driver default.
+        fn expected(&self, power_defaults: &HashMap<u32, u32>) -> LiveSettings {
+            if !self.reset_unset {
+                return (self.sets.power_limit, self.sets.freq_offset, self.sets.mem_offset);
+            }
+            let default_power = power_defaults.get(&self.index).copied();
+            (self.sets.power_limit.or(default_power), self.sets.freq_offset.or(Some(0)), self.sets.mem_offset.or(Some(0)))
+        }
+
+        /// The equivalent CLI command, quoted for a shell.
+        fn preview(&self) -> String {
+            let mut command = vec![Self::exe()];
+            command.extend(self.args());
+            crate::init::shell_quote(&command)
+        }
+
+        /// The outcomes a `--json` command like `zelos set --json` printed.
+        fn outcomes(out: &std::process::Output) -> Option<Vec<Outcome>> {
+            String::from_utf8_lossy(&out.stdout).lines().rev().find_map(|line| serde_json::from_str(line).ok())
+        }
+
+        /// The outcomes printed by a `--json` command, for the activity log.
+        fn outcome_lines(out: &std::process::Output) -> Vec<String> {
+            Self::outcomes(out).unwrap_or_default().iter().map(|o| o.to_string()).collect()
+        }
+
+        /// Why a `--json` command failed: the settings that were rejected,
+        /// or else its stderr or exit status.
+        fn failure(out: &std::process::Output) -> String {
+            let failed: Vec<String> = Self::outcomes(out).unwrap_or_default().iter().filter(|o| o.error.is_some()).map(|o| o.to_string()).collect();
+            if !failed.is_empty() {
+                return failed.join("\n");
+            }
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            if stderr.trim().is_empty() { tr!("Process exited with status: {}", out.status) } else { stderr.trim().to_string() }
+        }
+
+        /// Runs the request, logging it, and returns the outcome of every
+        /// setting, or why no setting could be tried at all (elevation
+        /// denied, driver missing, ...).
+        fn run(&self, log: &ActivityLog) -> Result<Vec<Outcome>, String> {
+            let out = log
+                .run(&tr!("Apply settings to GPU {}", self.index), std::process::Command::new("pkexec").arg(Self::exe()).args(self.args()).arg("--json"), Self::outcome_lines)
+                .map_err(|e| tr!("Failed to run pkexec: {}", e))?;
+            Self::outcomes(&out).ok_or_else(|| Self::failure(&out))
+        }
+    }
+
+    /// Whether every setting that went from `prev` to `now` reached what
+    /// this GUI asked for in `expected`. Matched requests are used up, so
+    /// an outside change back to the same value later isn't taken for ours.
+    fn changed_by_us(prev: LiveSettings, now: LiveSettings, expected: &mut LiveSettings) -> bool {
+        fn check<T: PartialEq>(prev: Option<T>, now: Option<T>, expected: &mut Option<T>) -> bool {
+            if prev == now {
+                return true;
+            }
+            let ours = expected.is_some() && *expected == now;
+            if ours {
+                *expected = None;
+            }
+            ours
+        }
+        let power = check(prev.0, now.0, &mut expected.0);
+        let freq = check(prev.1, now.1, &mut expected.1);
+        let mem = check(prev.2, now.2, &mut expected.2);
+        power && freq && mem
+    }
+
+    /// Replaces the config file with `config` through `zelos save` under
+    /// pkexec, also installing the boot job if `install_service` is set.
+    /// If elevation is denied, nothing is written.
+    fn save_config(config_path: &str, config: &Config, install_service: bool, log: &ActivityLog) -> Result<(), String> {
+        // Created exclusively and readable only by us, so nobody can swap in
+        // their own config for the elevated CLI to save.
+        let tmp = tempfile::Builder::new()
+            .prefix("zelos-config-")
+            .suffix(".json")
+            .tempfile()
+            .and_then(|mut tmp| {
+                serde_json::to_writer_pretty(&mut tmp, config)?;
+                Ok(tmp)
+            })
+            .map_err(|e| tr!("Failed to write temp config file: {}", e))?;
+
+        let mut command = std::process::Command::new("pkexec");
+        command.arg(ApplyRequest::exe()).arg("--file").arg(config_path).arg("save");
+        if install_service {
+            command.arg("--install-service");
+        }
+        command.arg(tmp.path());
+        let action = if install_service { tr!("Save config and install the boot job") } else { tr!("Save config") };
+        let result = log.run(&action, &mut command, |_| Vec::new());
+
+        // Cleanup the temporary config regardless of the outcome.
+        drop(tmp);
+
+        let out = result.map_err(|e| tr!("Failed to run pkexec: {}", e))?;
+        if out.status.success() {
+            return Ok(());
+        }
+        let mut msg = String::new();
+        if !out.stdout.is_empty() {
+            msg.push_str(&String::from_utf8_lossy(&out.stdout));
+        }
+        if !out.stderr.is_empty() {
+            if !msg.is_empty() { msg.push('\n'); }
+            msg.push_str(&String::from_utf8_lossy(&out.stderr));
+        }
+        if msg.is_empty() {
+            msg = tr!("Process exited with status: {}", out.status);
+        }
+        Err(msg)
+    }
+
+    /// Asks for a name, calling `on_name` with the trimmed, non-empty answer.
+    fn prompt_name<P: gtk4::prelude::IsA<gtk4::Window> + Clone + 'static>(parent: Option<&P>, text: &str, initial: &str, on_name: impl Fn(String) + 'static) {
+        let dlg = MessageDialog::new(parent, gtk4::DialogFlags::MODAL, MessageType::Question, ButtonsType::OkCancel, text);
+        let entry = Entry::new();
+        entry.set_text(initial);
+        entry.set_activates_default(true);
+        dlg.set_default_response(gtk4::ResponseType::Ok);
+        if let Ok(area) = dlg.message_area().downcast::<GtkBox>() {
+            area.append(&entry);
+        }
+        dlg.connect_response(move |d, resp| {
+            d.close();
+            let name = entry.text().trim().to_string();
+            if resp == gtk4::ResponseType::Ok && !name.is_empty() {
+                on_name(name);
+            }
+        });
+        dlg.present();
+    }
+
+    /// An extra choice in a file dialog: id, label and (id, label) options.
+    type FileChoice<'a> = (&'a str, &'a str, &'a [(&'a str, &'a str)]);
+
+    /// Asks where to save a file named `name` by default, then calls `save`
+    /// with the path and the dialog (for its choices), reporting any error.
+    fn save_file_dialog<P: gtk4::prelude::IsA<gtk4::Window> + Clone + 'static>(
+        parent: Option<&P>,
+        title: &str,
+        name: &str,
+        choices: &[FileChoice],
+        save: impl Fn(&std::path::Path, &gtk4::FileChooserNative) -> Result<(), String> + 'static,
+    ) {
+        let dlg = gtk4::FileChooserNative::new(Some(title), parent, gtk4::FileChooserAction::Save, Some(&tr!("Save")), Some(&tr!("Cancel")));
+        dlg.set_modal(true);
+        dlg.set_current_name(name);
+        for (id, label, options) in choices {
+            dlg.add_choice(*id, *label, options);
+            if let Some((first, _)) = options.first() {
+                dlg.set_choice(id, first);
+            }
+        }
+        let parent = parent.map(|p| p.clone().upcast::<gtk4::Window>());
+        // The dialog is only referenced by its own handler until it responds.
+        let keep = RefCell::new(Some(dlg.clone()));
+        dlg.connect_response(move |d, resp| {
+            keep.borrow_mut().take();
+            if resp != gtk4::ResponseType::Accept {
+                return;
+            }
+            let Some(path) = d.file().and_then(|f| f.path()) else {
+                return;
+            };
+            if let Err(e) = save(&path, d) {
+                show_message(parent.as_ref(), MessageType::Error, ButtonsType::Ok, &tr!("Failed to save {}: {}", path.display(), e));
+            }
+        });
+        dlg.show();
+    }
+
+    /// Name and UUID of each GPU in `gpus` (index and label), for exports.
+    fn gpu_identities(gpus: &[(String, String)]) -> Vec<GpuIdentity> {
+        let nvml = nvml_wrapper::Nvml::init().ok();
+        gpus.iter()
+            .filter_map(|(id, label)| {
+                let index: u32 = id.parse().ok()?;
+                let dev = nvml.as_ref().and_then(|n| n.device_by_index(index).ok());
+                Some(GpuIdentity {
+                    index,
+                    name: dev.as_ref().and_then(|d| d.name().ok()).unwrap_or_else(|| label.clone()),
+                    uuid: dev.as_ref().and_then(|d| d.uuid().ok()).unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Sends `(index, detail)` for every critical Xid error NVML reports.
+    /// Blocks, so it runs on its own thread; returns if events aren't
+    /// supported or the receiver is gone.
+    fn watch_xid_errors(tx: glib::Sender<(u32, String)>) {
+        use nvml_wrapper::{bitmasks::event::EventTypes, enums::event::XidError, error::NvmlError};
+
+        let Ok(nvml) = nvml_wrapper::Nvml::init() else {
+            return;
+        };
+        let Ok(mut set) = nvml.create_event_set() else {
+            return;
+        };
+        let mut watched = false;
+        for index in 0..nvml.device_count().unwrap_or(0) {
+            let Ok(dev) = nvml.device_by_index(index) else {
+                continue;
+            };
+            if !dev.supported_event_types().map(|t| t.contains(EventTypes::CRITICAL_XID_ERROR)).unwrap_or(false) {
+                continue;
+            }
+            set = match dev.register_events(EventTypes::CRITICAL_XID_ERROR, set) {
+                Ok(set) => set,
+                Err(_) => return,
+            };
+            watched = true;
+        }
+        if !watched {
+            return;
+        }
+        loop {
+            match set.wait(5000) {
+                Ok(event) => {
+                    let detail = match event.event_data {
+                        Some(XidError::Value(xid)) => tr!("Xid {}", xid),
+                        _ => tr!("Unknown Xid"),
+                    };
+                    if tx.send((event.device.index().unwrap_or(0), detail)).is_err() {
+                        return;
+                    }
+                }
+                Err(NvmlError::Timeout) => {}
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// Entries of the tray menu: open, the profiles of each GPU, resets to
+    /// driver defaults, and whether closing the window keeps the tray.
+    fn tray_menu(config: &Config, gpus: &[(String, String)], keep_running: bool) -> Vec<MenuItem> {
+        let indices: Vec<u32> = gpus.iter().filter_map(|(id, _)| id.parse().ok()).collect();
+        let multi = indices.len() > 1;
+        let mut items = vec![MenuItem::new(tr!("Open zelos"), ItemKind::Normal, Some(TrayAction::Open)), MenuItem::separator()];
+        for &index in &indices {
+            let active = config.active_profiles.get(&index);
+            for name in config.profiles.get(&index).into_iter().flat_map(|p| p.keys()) {
+                let label = if multi { tr!("GPU {}: {}", index, name) } else { name.clone() };
+                items.push(MenuItem::new(label, ItemKind::Radio(active == Some(name)), Some(TrayAction::ApplyProfile(index, name.clone()))));
+            }
+        }
+        if items.len() > 2 {
+            items.push(MenuItem::separator());
+        }
+        for &index in &indices {
+            let label = if multi { tr!("Reset GPU {} to Defaults", index) } else { tr!("Reset to Defaults") };
+            items.push(MenuItem::new(label, ItemKind::Normal, Some(TrayAction::Reset(index))));
+        }
+        items.push(MenuItem::separator());
+        items.push(MenuItem::new(tr!("Keep Running When Closed"), ItemKind::Check(keep_running), Some(TrayAction::KeepRunning(!keep_running))));
+        items.push(MenuItem::new(tr!("Quit"), ItemKind::Normal, Some(TrayAction::Quit)));
+        items
+    }
+
+    /// Memory clocks GPU `index` can be locked to, lowest first. Empty if
+    /// NVML can't report them.
+    fn supported_mem_clocks(index: u32) -> Vec<u32> {
+        let mut clocks = nvml_wrapper::Nvml::init()
+            .ok()
+            .and_then(|nvml| nvml.device_by_index(index).ok().and_then(|d| d.supported_memory_clocks().ok()))
+            .unwrap_or_default();
+        clocks.sort_unstable();
+        clocks.dedup();
+        clocks
+    }
+
+    /// What the selected GPU accepts, used to bound the Performance tab's
+    /// controls. Anything NVML can't report keeps the built-in range.
+    struct DeviceLimits {
+        /// Power limit range in W
+        power: (f64, f64),
+        /// Default power limit in W
+        power_default: Option<f64>,
+        freq_offset: (f64, f64),
+        mem_offset: (f64, f64),
+        /// Lowest and highest lockable core clock in MHz
+        core_clock: (f64, f64),
+        /// Lowest and highest lockable memory clock in MHz
+        mem_clock: (f64, f64),
+    }
+
+    impl Default for DeviceLimits {
+        fn default() -> Self {
+            Self {
+                power: (0.0, 450.0),
+                power_default: None,
+                freq_offset: (-2000.0, 2000.0),
+                mem_offset: (-20000.0, 20000.0),
+                core_clock: (0.0, 5000.0),
+                mem_clock: (0.0, 20000.0),
+            }
+        }
+    }
+
+    impl DeviceLimits {
+        fn query(index: u32, mem_clocks: &[u32]) -> Self {
+            use nvml_wrapper::enum_wrappers::device::{Clock, PerformanceState};
+
+            let mut limits = Self::default();
+            let Some(nvml) = nvml_wrapper::Nvml::init().ok() else {
+                return limits;
+            };
+            let Ok(device) = nvml.device_by_index(index) else {
+                return limits;
+            };
+
+            if let Ok(c) = device.power_management_limit_constraints() {
+                limits.power = (c.min_limit as f64 / 1000.0, c.max_limit as f64 / 1000.0);
+            }
+            limits.power_default = device.power_management_limit_default().ok().map(|d| d as f64 / 1000.0);
+
+            // Offsets are applied to the highest performance state.
+            if let Ok(o) = device.clock_offset(Clock::Graphics, PerformanceState::Zero) {
+                limits.freq_offset = (o.min_clock_offset_mhz as f64, o.max_clock_offset_mhz as f64);
+            }
+            if let Ok(o) = device.clock_offset(Clock::Memory, PerformanceState::Zero) {
+                limits.mem_offset = (o.min_clock_offset_mhz as f64, o.max_clock_offset_mhz as f64);
+            }
+
+            let core_clocks = mem_clocks.last().and_then(|&mem| device.supported_graphics_clocks(mem).ok()).unwrap_or_default();
+            match (core_clocks.iter().min(), core_clocks.iter().max()) {
+                (Some(&lo), Some(&hi)) => limits.core_clock = (lo as f64, hi as f64),
+                _ => {
+                    if let Ok(max) = device.max_clock_info(Clock::Graphics) {
+                        limits.core_clock.1 = max as f64;
+                    }
+                }
+            }
+            match (mem_clocks.first(), mem_clocks.last()) {
+                (Some(&lo), Some(&hi)) => limits.mem_clock = (lo as f64, hi as f64),
+                _ => {
+                    if let Ok(max) = device.max_clock_info(Clock::Memory) {
+                        limits.mem_clock.1 = max as f64;
+                    }
+                }
+            }
+            limits
+        }
+
+        /// Bounds `controls` to these limits and marks the default power
+        /// limit on `power_scale`.
+        fn apply(&self, controls: &PerfControls, power_scale: &Scale, power_sub: &Label) {
+            let bound = |adj: &Adjustment, (lo, hi): (f64, f64)| {
+                adj.configure(adj.value().clamp(lo, hi), lo, hi, adj.step_increment(), adj.page_increment(), adj.page_size());
+            };
+            bound(&controls.power, self.power);
+            bound(&controls.freq, self.freq_offset);
+            bound(&controls.mem, self.mem_offset);
+            bound(&controls.min_clock, self.core_clock);
+            bound(&controls.max_clock, self.core_clock);
+            bound(&controls.min_mem_clock, self.mem_clock);
+            bound(&controls.max_mem_clock, self.mem_clock);
+
+            power_scale.clear_marks();
+            match self.power_default {
+                Some(default) => {
+                    power_scale.add_mark(default, gtk4::PositionType::Bottom, Some(&tr!("Default")));
+                    power_sub.set_text(&tr!("{}–{} W, default {} W", number(self.power.0, 0), number(self.power.1, 0), number(default, 0)));
+                }
+                None => power_sub.set_text(&tr!("Max {} W", number(self.power.1, 0))),
+            }
+        }
+    }
+
+    /// Snaps `value` to an entry of the sorted clock `table`. Moving away
+    /// from `prev` picks the next entry in that direction, so the steppers
+    /// walk the table instead of getting pulled back to the same clock.
+    fn snap_clock(table: &[u32], prev: f64, value: f64) -> Option<f64> {
+        let (first, last) = (*table.first()? as f64, *table.last()? as f64);
+        let snapped = if value > prev {
+            table.iter().map(|&c| c as f64).find(|&c| c >= value).unwrap_or(last)
+        } else if value < prev {
+            table.iter().rev().map(|&c| c as f64).find(|&c| c <= value).unwrap_or(first)
+        } else {
+            table.iter().map(|&c| c as f64).min_by(|a, b| (a - value).abs().total_cmp(&(b - value).abs())).unwrap_or(value)
+        };
+        Some(snapped)
     }
 
     fn list_nvidia_gpus() -> Vec<(String, String)> {
@@ -26,7 +483,7 @@ pub mod imp {
                             let name_part = rest[colon_pos + 1..].trim();
                             let name = name_part.split('(').next().unwrap_or(name_part).trim().to_string();
                             let idx_clone = idx.clone();
-                            v.push((idx, format!("GPU {}: {}", idx_clone, name)));
+                            v.push((idx, tr!("GPU {}: {}", idx_clone, name)));
                         }
                     }
                 }
@@ -35,7 +492,7 @@ pub mod imp {
                 }
             }
         }
-        vec![("0".to_string(), "GPU 0 (default)".to_string())]
+        vec![("0".to_string(), tr!("GPU {} (default)", 0))]
     }
 
     fn show_message<P: gtk4::prelude::IsA<gtk4::Window> + Clone + 'static>(parent: Option<&P>, mtype: MessageType, buttons: ButtonsType, text: &str) {
@@ -50,84 +507,180 @@ pub mod imp {
         dlg.present();
     }
 
-    pub fn run(_config_path: &str) {
-        // Pre-parse existing systemd service ExecStart (if present) to pre-fill fields
-        use std::os::unix::fs::PermissionsExt;
-        let svc_path = "/etc/systemd/system/zelos.service";
-        let mut svc_index: Option<String> = None;
-        let mut svc_power: Option<i32> = None;
-        let mut svc_freq: Option<i32> = None;
-        let mut svc_mem: Option<i32> = None;
-        let mut svc_min: Option<i32> = None;
-        let mut svc_max: Option<i32> = None;
-        if std::path::Path::new(svc_path).exists() {
-            if let Ok(s) = std::fs::read_to_string(svc_path) {
-                for line in s.lines() {
-                    let line = line.trim();
-                    if line.starts_with("ExecStart=") {
-                        let mut exec = line.trim_start_matches("ExecStart=").trim().to_string();
-                        if exec.starts_with('"') && exec.ends_with('"') && exec.len() > 1 {
-                            exec = exec[1..exec.len() - 1].to_string();
-                        }
-                        let parts: Vec<&str> = exec.split_whitespace().collect();
-                        let mut after_set = false;
-                        let mut i = 0usize;
-                        while i < parts.len() {
-                            let p = parts[i];
-                            if !after_set {
-                                if p == "set" {
-                                    after_set = true;
-                                }
-                                i += 1;
-                                continue;
-                            }
-                            match p {
-                                "--index" => {
-                                    if i + 1 < parts.len() {
-                                        svc_index = Some(parts[i + 1].to_string());
-                                    }
-                                    i += 2;
-                                }
-                                "--power-limit" => {
-                                    if i + 1 < parts.len() {
-                                        svc_power = parts[i + 1].parse().ok();
-                                    }
-                                    i += 2;
-                                }
-                                "--freq-offset" => {
-                                    if i + 1 < parts.len() {
-                                        svc_freq = parts[i + 1].parse().ok();
-                                    }
-                                    i += 2;
-                                }
-                                "--mem-offset" => {
-                                    if i + 1 < parts.len() {
-                                        svc_mem = parts[i + 1].parse().ok();
-                                    }
-                                    i += 2;
-                                }
-                                "--min-clock" => {
-                                    if i + 1 < parts.len() {
-                                        svc_min = parts[i + 1].parse().ok();
-                                    }
-                                    i += 2;
-                                }
-                                "--max-clock" => {
-                                    if i + 1 < parts.len() {
-                                        svc_max = parts[i + 1].parse().ok();
-                                    }
-                                    i += 2;
-                                }
-                                _ => {
-                                    i += 1;
-                                }
-                            }
-                        }
-                    }
-                }
+    /// Starting points for the fan curve editor, as (°C, %) pairs.
+    const FAN_PRESETS: [(&str, &[(u32, u32)]); 3] = [
+        ("Quiet", &[(30, 20), (50, 30), (65, 45), (75, 65), (85, 100)]),
+        ("Balanced", &[(30, 30), (50, 40), (60, 55), (70, 75), (80, 100)]),
+        ("Aggressive", &[(30, 40), (45, 55), (55, 70), (65, 90), (75, 100)]),
+    ];
+
+    fn fan_preset(points: &[(u32, u32)]) -> Vec<FanPoint> {
+        points.iter().map(|&(temp, speed)| FanPoint { temp, speed }).collect()
+    }
+
+    /// Maps between fan curve points and fan editor pixels. The editor spans
+    /// 20–100 °C horizontally and 0–100 % vertically.
+    struct FanPlot {
+        left: f64,
+        top: f64,
+        w: f64,
+        h: f64,
+    }
+
+    impl FanPlot {
+        const T_MIN: f64 = 20.0;
+        const T_MAX: f64 = 100.0;
+
+        fn new(width: f64, height: f64) -> Self {
+            let (left, right, top, bottom) = (44.0, 14.0, 12.0, 28.0);
+            Self { left, top, w: (width - left - right).max(1.0), h: (height - top - bottom).max(1.0) }
+        }
+
+        fn point_to_xy(&self, temp: f64, speed: f64) -> (f64, f64) {
+            let x = self.left + ((temp - Self::T_MIN) / (Self::T_MAX - Self::T_MIN)).clamp(0.0, 1.0) * self.w;
+            let y = self.top + (1.0 - (speed / 100.0).clamp(0.0, 1.0)) * self.h;
+            (x, y)
+        }
+
+        fn xy_to_point(&self, x: f64, y: f64) -> (f64, f64) {
+            let temp = Self::T_MIN + ((x - self.left) / self.w) * (Self::T_MAX - Self::T_MIN);
+            let speed = (1.0 - (y - self.top) / self.h) * 100.0;
+            (temp, speed)
+        }
+    }
+
+    /// Lists the outcome of every setting of an apply, marking failures.
+    fn show_outcomes<P: gtk4::prelude::IsA<gtk4::Window> + Clone + 'static>(parent: Option<&P>, outcomes: &[Outcome]) {
+        let failed = outcomes.iter().filter(|o| o.error.is_some()).count();
+        let (mtype, text) = match failed {
+            0 => (MessageType::Info, tr!("Applied {} setting(s)", outcomes.len())),
+            n => (MessageType::Error, tr!("{} of {} setting(s) failed", n, outcomes.len())),
+        };
+        let parent_clone = parent.map(|p| p.clone().upcast::<gtk4::Window>());
+        let dlg = MessageDialog::new(parent, gtk4::DialogFlags::MODAL, mtype, ButtonsType::Ok, &text);
+
+        let list = gtk4::ListBox::new();
+        list.set_selection_mode(gtk4::SelectionMode::None);
+        list.set_css_classes(&["boxed-list"]);
+        for outcome in outcomes {
+            let row = GtkBox::new(Orientation::Horizontal, 10);
+            row.set_css_classes(&["perf-row"]);
+            let icon = Image::from_icon_name(if outcome.error.is_none() { "emblem-ok-symbolic" } else { "dialog-error-symbolic" });
+            let label = Label::new(Some(&outcome.to_string()));
+            label.set_halign(gtk4::Align::Start);
+            label.set_wrap(true);
+            label.set_xalign(0.0);
+            row.append(&icon);
+            row.append(&label);
+            list.append(&row);
+        }
+        if let Ok(area) = dlg.message_area().downcast::<GtkBox>() {
+            area.append(&list);
+        }
+
+        dlg.connect_response(move |d, _| {
+            d.close();
+            if let Some(ref p) = parent_clone {
+                p.present();
+            }
+        });
+        dlg.present();
+    }
+
+    /// The Performance tab controls that map onto a GPU's `Sets`.
+    #[derive(Clone)]
+    struct PerfControls {
+        power: Adjustment,
+        freq: Adjustment,
+        mem: Adjustment,
+        lock_core: gtk4::Switch,
+        min_clock: Adjustment,
+        max_clock: Adjustment,
+        lock_mem: gtk4::Switch,
+        min_mem_clock: Adjustment,
+        max_mem_clock: Adjustment,
+    }
+
+    impl PerfControls {
+        /// Shows `sets` in the controls. `mem_clocks` supplies the memory
+        /// clock range for GPUs whose memory clocks aren't locked.
+        fn load(&self, sets: &Sets, mem_clocks: &[u32]) {
+            self.power.set_value(sets.power_limit.unwrap_or(400_000) as f64 / 1000.0);
+            self.freq.set_value(sets.freq_offset.unwrap_or(0) as f64);
+            self.mem.set_value(sets.mem_offset.unwrap_or(0) as f64);
+            self.lock_core.set_active(sets.min_clock.is_some() && sets.max_clock.is_some());
+            self.min_clock.set_value(sets.min_clock.unwrap_or(0) as f64);
+            self.max_clock.set_value(sets.max_clock.unwrap_or(3800) as f64);
+            self.lock_mem.set_active(sets.min_mem_clock.is_some() && sets.max_mem_clock.is_some());
+            self.min_mem_clock.set_value(sets.min_mem_clock.or(mem_clocks.first().copied()).unwrap_or(0) as f64);
+            self.max_mem_clock.set_value(sets.max_mem_clock.or(mem_clocks.last().copied()).unwrap_or(0) as f64);
+        }
+
+        /// The controls' values. Clock ranges whose lock is off are left
+        /// out, so they aren't locked when applied.
+        fn read(&self) -> Sets {
+            let core_locked = self.lock_core.is_active();
+            let mem_locked = self.lock_mem.is_active();
+            Sets {
+                power_limit: Some((self.power.value() * 1000.0).round() as u32),
+                freq_offset: Some(self.freq.value() as i32),
+                mem_offset: Some(self.mem.value() as i32),
+                min_clock: core_locked.then(|| self.min_clock.value() as u32),
+                max_clock: core_locked.then(|| self.max_clock.value() as u32),
+                min_mem_clock: mem_locked.then(|| self.min_mem_clock.value() as u32),
+                max_mem_clock: mem_locked.then(|| self.max_mem_clock.value() as u32),
             }
         }
 
+        /// Calls `f` whenever any control changes.
+        fn connect_changed<F: Fn() + Clone + 'static>(&self, f: F) {
+            for adj in [&self.power, &self.freq, &self.mem, &self.min_clock, &self.max_clock, &self.min_mem_clock, &self.max_mem_clock] {
+                let f = f.clone();
+                adj.connect_value_changed(move |_| f());
+            }
+            for switch in [&self.lock_core, &self.lock_mem] {
+                let f = f.clone();
+                switch.connect_active_notify(move |_| f());
+            }
+        }
+    }
+
+    /// Values to show for GPU `index`: the live offsets and power limit from
+    /// NVML when it can be queried, the saved config for everything else.
+    /// The flag is false if NVML couldn't be queried.
+    fn form_sets(index: u32, saved: Option<&Sets>) -> (Sets, bool) {
+        let mut sets = saved.cloned().unwrap_or_default();
+        let Ok(nvml) = nvml_wrapper::Nvml::init() else {
+            return (sets, false);
+        };
+        let Ok(device) = nvml.device_by_index(index) else {
+            return (sets, false);
+        };
+        if let Ok(limit) = device.enforced_power_limit() {
+            sets.power_limit = Some(limit);
+        }
+        if let Ok(freq) = device.gpc_clock_vf_offset() {
+            sets.freq_offset = Some(freq);
+        }
+        if let Ok(mem) = device.mem_clock_vf_offset() {
+            sets.mem_offset = Some(mem);
+        }
+        (sets, true)
+    }
+
+    pub fn run(config_path: &str) {
+        i18n::init();
+
+        // The Performance tab edits the per-GPU `sets` of the zelos config.
+        // A missing file just means nothing has been saved yet.
+        let (saved_config, config_error) = match std::fs::read_to_string(config_path) {
+            Ok(s) => match serde_json::from_str::<Config>(&s) {
+                Ok(config) => (config, None),
+                Err(e) => (Config::default(), Some(tr!("Could not parse {}: {}. Saving will replace it.", config_path, e))),
+            },
+            Err(_) => (Config::default(), None),
+        };
+
         // Initialize GTK early so we can override problematic GtkSettings *before*
         // libadwaita initializes (prevents the warning).
         let _ = gtk4::init();
@@ -144,8 +697,18 @@ pub mod imp {
         adw::StyleManager::default().set_color_scheme(adw::ColorScheme::Default);
 
         let app = adw::Application::new(Some("org.github.kombatant.zelos"), Default::default());
+        let config_path = config_path.to_string();
+        let saved_config = Rc::new(RefCell::new(saved_config));
 
         app.connect_activate(move |app| {
+            // Launching again while the window is hidden in the tray shows it.
+            if let Some(window) = app.windows().first() {
+                window.present();
+                return;
+            }
+            let prefs = Rc::new(RefCell::new(Preferences::load()));
+            let activity = Rc::new(ActivityLog::new());
+            let undo = Rc::new(RefCell::new(UndoHistory::default()));
             let window = adw::ApplicationWindow::new(app);
             // Explicitly set the window icon name so desktop environments
             // (KDE, GNOME, etc.) can match the running window to the
@@ -168,53 +731,39 @@ pub mod imp {
             // GPU selector (reused inside the device card)
             let gpu_combo = ComboBoxText::new();
             gpu_combo.set_hexpand(true);
-            for (id, label) in list_nvidia_gpus() {
-                gpu_combo.append(Some(&id), &label);
+            let gpu_ids: Vec<u32> = list_nvidia_gpus()
+                .into_iter()
+                .filter_map(|(id, label)| {
+                    gpu_combo.append(Some(&id), &label);
+                    id.parse().ok()
+                })
+                .collect();
+            // Start on the GPU selected last time, if it's still there.
+            let last_gpu = prefs.borrow().last_gpu.filter(|i| gpu_ids.contains(i));
+            let gpu_index_num: u32 = last_gpu.or_else(|| saved_config.borrow().sets.keys().min().copied()).unwrap_or(0);
+            gpu_combo.set_active_id(Some(&gpu_index_num.to_string()));
+            // The GPU the Performance form and the Metrics tab show.
+            let form_gpu = Rc::new(Cell::new(gpu_index_num));
+
+            // Prefer the live GPU settings over the saved config, so the tab
+            // shows what the card is actually running.
+            let (initial_sets, nvml_available) = form_sets(gpu_index_num, saved_config.borrow().sets.get(&gpu_index_num));
+            if !nvml_available {
+                show_message(Some(&window), MessageType::Warning, ButtonsType::Ok, &tr!("Warning: could not query NVML — using config values or defaults."));
             }
-            if let Some(ref idx) = svc_index {
-                gpu_combo.set_active_id(Some(idx));
-            } else {
-                gpu_combo.set_active_id(Some("0"));
-            }
-
-            // Try to read current GPU settings via NVML and prefer those over
-            // values stored in the systemd service. If NVML is unavailable or
-            // a query fails, fall back to the service values and then to
-            // sensible defaults.
-            let mut current_power: Option<i32> = None; // milliwatts
-            let mut current_freq: Option<i32> = None; // MHz
-            let mut current_mem: Option<i32> = None; // MHz
-            let mut nvml_available = true;
-            let gpu_index_num: u32 = svc_index.as_deref().and_then(|s| s.parse().ok()).unwrap_or(0);
-            match nvml_wrapper::Nvml::init() {
-                Ok(nvml) => {
-                    match nvml.device_by_index(gpu_index_num) {
-                        Ok(device) => {
-                            if let Ok(limit) = device.enforced_power_limit() {
-                                current_power = Some(limit as i32);
-                            }
-                            if let Ok(freq) = device.gpc_clock_vf_offset() {
-                                current_freq = Some(freq);
-                            }
-                            if let Ok(mem) = device.mem_clock_vf_offset() {
-                                current_mem = Some(mem);
-                            }
-                        }
-                        Err(_) => {
-                            nvml_available = false;
-                        }
-                    }
-                }
-                Err(_) => {
-                    nvml_available = false;
-                }
+            if let Some(ref message) = config_error {
+                show_message(Some(&window), MessageType::Warning, ButtonsType::Ok, message);
             }
-            if !nvml_available {
-                show_message(Some(&window), MessageType::Warning, ButtonsType::Ok, "Warning: could not query NVML — using service values or defaults.");
+
+            // Let the user know if boot-loop protection kept their settings
+            // from being applied at boot.
+            let guard_config = crate::boot_guard::BootGuardConfig::load(&config_path);
+            if let Some(message) = crate::boot_guard::BootGuardState::load(&guard_config.state_file).tripped_message() {
+                show_message(Some(&window), MessageType::Warning, ButtonsType::Ok, &message);
             }
 
             // --- Device selection section ---
-            let device_section_title = Label::new(Some("Device Selection"));
+            let device_section_title = Label::new(Some(&tr!("Device Selection")));
             device_section_title.set_halign(gtk4::Align::Start);
             device_section_title.set_css_classes(&["perf-section-title"]);
             perf_box.append(&device_section_title);
@@ -239,11 +788,11 @@ pub mod imp {
 
             let device_texts = GtkBox::new(Orientation::Vertical, 2);
             device_texts.set_valign(gtk4::Align::Center);
-            let device_title = Label::new(Some("Target GPU"));
+            let device_title = Label::new(Some(&tr!("Target GPU")));
             device_title.set_halign(gtk4::Align::Start);
             device_title.set_valign(gtk4::Align::Center);
             device_title.set_css_classes(&["perf-card-title"]);
-            let device_sub = Label::new(Some("Select the card to overclock"));
+            let device_sub = Label::new(Some(&tr!("Select the card to overclock")));
             device_sub.set_halign(gtk4::Align::Start);
             device_sub.set_valign(gtk4::Align::Center);
             device_sub.set_css_classes(&["perf-card-subtitle"]);
@@ -263,17 +812,14 @@ pub mod imp {
             perf_box.append(&device_card);
 
             // --- Power configuration section ---
-            let power_section_title = Label::new(Some("Power Configuration"));
+            let power_section_title = Label::new(Some(&tr!("Power Configuration")));
             power_section_title.set_halign(gtk4::Align::Start);
             power_section_title.set_css_classes(&["perf-section-title"]);
             power_section_title.set_margin_top(4);
             perf_box.append(&power_section_title);
 
-            let power_initial = match current_power {
-                Some(p) => p as f64 / 1000.0,
-                None => svc_power.unwrap_or(400_000) as f64 / 1000.0,
-            };
-            // Match the redesign (common 450W max on many cards)
+            let power_initial = initial_sets.power_limit.unwrap_or(400_000) as f64 / 1000.0;
+            // Placeholder range until `DeviceLimits` bounds it to the GPU.
             let power_adj = Adjustment::new(power_initial, 0.0, 450.0, 0.1, 1.0, 0.0);
             let power_scale = Scale::new(Orientation::Horizontal, Some(&power_adj));
             power_scale.set_hexpand(true);
@@ -303,11 +849,11 @@ pub mod imp {
 
             let power_texts = GtkBox::new(Orientation::Vertical, 2);
             power_texts.set_valign(gtk4::Align::Center);
-            let power_title = Label::new(Some("Power Limit (W)"));
+            let power_title = Label::new(Some(&tr!("Power Limit (W)")));
             power_title.set_halign(gtk4::Align::Start);
             power_title.set_valign(gtk4::Align::Center);
             power_title.set_css_classes(&["perf-card-title"]);
-            let power_sub = Label::new(Some(&format!("Max {}W", power_adj.upper().round() as i32)));
+            let power_sub = Label::new(Some(&tr!("Max {} W", number(power_adj.upper(), 0))));
             power_sub.set_halign(gtk4::Align::Start);
             power_sub.set_valign(gtk4::Align::Center);
             power_sub.set_css_classes(&["perf-card-subtitle"]);
@@ -328,16 +874,53 @@ pub mod imp {
             power_card.append(&power_scale);
             perf_box.append(&power_card);
 
-            let freq_initial = current_freq.unwrap_or(svc_freq.unwrap_or(0));
+            let freq_initial = initial_sets.freq_offset.unwrap_or(0);
             let freq_adj = Adjustment::new(freq_initial as f64, -2000.0, 2000.0, 1.0, 10.0, 0.0);
-            let mem_initial = current_mem.unwrap_or(svc_mem.unwrap_or(0));
+            let mem_initial = initial_sets.mem_offset.unwrap_or(0);
             let mem_adj = Adjustment::new(mem_initial as f64, -20000.0, 20000.0, 1.0, 10.0, 0.0);
 
-            let min_initial = svc_min.unwrap_or(0);
+            let min_initial = initial_sets.min_clock.unwrap_or(0);
             let min_adj = Adjustment::new(min_initial as f64, 0.0, 5000.0, 1.0, 10.0, 0.0);
-            let max_initial = svc_max.unwrap_or(3800);
+            let max_initial = initial_sets.max_clock.unwrap_or(3800);
             let max_adj = Adjustment::new(max_initial as f64, 0.0, 5000.0, 1.0, 10.0, 0.0);
 
+            // Memory clocks can only be locked to entries of the device's
+            // clock table, so the memory rows snap to it.
+            let mem_clocks = Rc::new(RefCell::new(supported_mem_clocks(gpu_index_num)));
+            let min_mem_initial = initial_sets.min_mem_clock.or(mem_clocks.borrow().first().copied()).unwrap_or(0);
+            let min_mem_adj = Adjustment::new(min_mem_initial as f64, 0.0, 20000.0, 1.0, 10.0, 0.0);
+            let max_mem_initial = initial_sets.max_mem_clock.or(mem_clocks.borrow().last().copied()).unwrap_or(0);
+            let max_mem_adj = Adjustment::new(max_mem_initial as f64, 0.0, 20000.0, 1.0, 10.0, 0.0);
+            for adj in [&min_mem_adj, &max_mem_adj] {
+                let prev = Cell::new(adj.value());
+                let mem_clocks = mem_clocks.clone();
+                adj.connect_value_changed(move |a| {
+                    let value = a.value();
+                    match snap_clock(&mem_clocks.borrow(), prev.get(), value) {
+                        Some(snapped) if snapped != value => a.set_value(snapped),
+                        _ => prev.set(value),
+                    }
+                });
+            }
+
+            let lock_core_switch = gtk4::Switch::new();
+            lock_core_switch.set_active(initial_sets.min_clock.is_some() && initial_sets.max_clock.is_some());
+            let lock_mem_switch = gtk4::Switch::new();
+            lock_mem_switch.set_active(initial_sets.min_mem_clock.is_some() && initial_sets.max_mem_clock.is_some());
+
+            let perf_controls = PerfControls {
+                power: power_adj.clone(),
+                freq: freq_adj.clone(),
+                mem: mem_adj.clone(),
+                lock_core: lock_core_switch.clone(),
+                min_clock: min_adj.clone(),
+                max_clock: max_adj.clone(),
+                lock_mem: lock_mem_switch.clone(),
+                min_mem_clock: min_mem_adj.clone(),
+                max_mem_clock: max_mem_adj.clone(),
+            };
+            DeviceLimits::query(gpu_index_num, &mem_clocks.borrow()).apply(&perf_controls, &power_scale, &power_sub);
+
             // Rows card (GPU freq/mem/min/max) with steppers
             let rows_card = GtkBox::new(Orientation::Vertical, 0);
             rows_card.set_css_classes(&["card", "perf-card"]);
@@ -424,20 +1007,66 @@ pub mod imp {
                 (row, value_entry)
             };
 
-            let (freq_row, _freq_val_lbl) = build_step_row("speedometer-symbolic", "GPU Freq Offset (MHz)", &freq_adj, 1.0);
+            let (freq_row, _freq_val_lbl) = build_step_row("speedometer-symbolic", &tr!("GPU Freq Offset (MHz)"), &freq_adj, 1.0);
             rows_card.append(&freq_row);
             rows_card.append(&Separator::new(Orientation::Horizontal));
 
-            let (mem_row, _mem_val_lbl) = build_step_row("media-floppy-symbolic", "Memory Offset (MHz)", &mem_adj, 1.0);
+            let (mem_row, _mem_val_lbl) = build_step_row("media-floppy-symbolic", &tr!("Memory Offset (MHz)"), &mem_adj, 1.0);
             rows_card.append(&mem_row);
             rows_card.append(&Separator::new(Orientation::Horizontal));
 
-            let (min_row, _min_val_lbl) = build_step_row("go-down-symbolic", "Min Clock (MHz)", &min_adj, 1.0);
+            // A row with a switch that enables the rows for a clock range.
+            let build_lock_row = |icon_name: &str, title: &str, switch: &gtk4::Switch, range_rows: [&GtkBox; 2]| -> GtkBox {
+                let row = GtkBox::new(Orientation::Horizontal, 12);
+                row.set_css_classes(&["perf-row"]);
+                row.set_hexpand(true);
+                row.set_valign(gtk4::Align::Center);
+
+                let icon = Image::from_icon_name(icon_name);
+                icon.set_pixel_size(18);
+                let badge = CenterBox::new();
+                badge.set_css_classes(&["perf-icon-badge"]);
+                badge.set_halign(gtk4::Align::Center);
+                badge.set_valign(gtk4::Align::Center);
+                badge.set_center_widget(Some(&icon));
+
+                let title_lbl = Label::new(Some(title));
+                title_lbl.set_halign(gtk4::Align::Start);
+                title_lbl.set_valign(gtk4::Align::Center);
+                title_lbl.set_hexpand(true);
+                title_lbl.set_css_classes(&["perf-row-title"]);
+
+                switch.set_valign(gtk4::Align::Center);
+                for range_row in range_rows {
+                    range_row.set_sensitive(switch.is_active());
+                    let range_row = range_row.clone();
+                    switch.connect_active_notify(move |s| range_row.set_sensitive(s.is_active()));
+                }
+
+                row.append(&badge);
+                row.append(&title_lbl);
+                row.append(switch);
+                row
+            };
+
+            let (min_row, _min_val_lbl) = build_step_row("go-down-symbolic", &tr!("Min Clock (MHz)"), &min_adj, 1.0);
+            let (max_row, _max_val_lbl) = build_step_row("go-up-symbolic", &tr!("Max Clock (MHz)"), &max_adj, 1.0);
+            let lock_core_row = build_lock_row("changes-prevent-symbolic", &tr!("Lock Core Clocks"), &lock_core_switch, [&min_row, &max_row]);
+            rows_card.append(&lock_core_row);
+            rows_card.append(&Separator::new(Orientation::Horizontal));
             rows_card.append(&min_row);
             rows_card.append(&Separator::new(Orientation::Horizontal));
-
-            let (max_row, _max_val_lbl) = build_step_row("go-up-symbolic", "Max Clock (MHz)", &max_adj, 1.0);
             rows_card.append(&max_row);
+            rows_card.append(&Separator::new(Orientation::Horizontal));
+
+            let (min_mem_row, _min_mem_val_lbl) = build_step_row("go-down-symbolic", &tr!("Min Memory Clock (MHz)"), &min_mem_adj, 1.0);
+            let (max_mem_row, _max_mem_val_lbl) = build_step_row("go-up-symbolic", &tr!("Max Memory Clock (MHz)"), &max_mem_adj, 1.0);
+            let lock_mem_row = build_lock_row("changes-prevent-symbolic", &tr!("Lock Memory Clocks"), &lock_mem_switch, [&min_mem_row, &max_mem_row]);
+            rows_card.append(&lock_mem_row);
+            rows_card.append(&Separator::new(Orientation::Horizontal));
+            rows_card.append(&min_mem_row);
+            rows_card.append(&Separator::new(Orientation::Horizontal));
+            rows_card.append(&max_mem_row);
 
             perf_box.append(&rows_card);
 
@@ -466,11 +1095,11 @@ pub mod imp {
 
             let preview_texts = GtkBox::new(Orientation::Vertical, 2);
             preview_texts.set_valign(gtk4::Align::Center);
-            let preview_title = Label::new(Some("Command Preview"));
+            let preview_title = Label::new(Some(&tr!("Command Preview")));
             preview_title.set_halign(gtk4::Align::Start);
             preview_title.set_valign(gtk4::Align::Center);
             preview_title.set_css_classes(&["perf-card-title"]);
-            let preview_sub = Label::new(Some("View generated CLI command"));
+            let preview_sub = Label::new(Some(&tr!("View generated CLI command")));
             preview_sub.set_halign(gtk4::Align::Start);
             preview_sub.set_valign(gtk4::Align::Center);
             preview_sub.set_css_classes(&["perf-card-subtitle"]);
@@ -514,18 +1143,54 @@ pub mod imp {
             actions.set_hexpand(true);
             actions.set_margin_top(2);
 
-            let service_btn = Button::with_label(if std::path::Path::new(svc_path).exists() { "Update Service" } else { "Create Service" });
+            // Boot-time apply job for whichever init system is running.
+            let service_exists = crate::init::Init::detect("/").map(|i| i.is_installed()).unwrap_or(false);
+            let service_btn = Button::with_label(&if service_exists { tr!("Update Service") } else { tr!("Create Service") });
             // Match the mock: secondary action with red/destructive emphasis.
             service_btn.set_css_classes(&["destructive-action", "perf-action-secondary"]);
 
-            let apply = Button::with_label("Apply Settings");
+            let apply = Button::with_label(&tr!("Apply Settings"));
             apply.set_css_classes(&["suggested-action", "perf-action-primary"]);
 
             let spacer = GtkBox::new(Orientation::Horizontal, 0);
             spacer.set_hexpand(true);
 
+            // States applied to the selected GPU, to go back to.
+            let undo_btn = Button::with_label(&tr!("Revert to Previous"));
+            let redo_btn = Button::with_label(&tr!("Redo"));
+            let undo_actions = GtkBox::new(Orientation::Horizontal, 6);
+            undo_actions.append(&undo_btn);
+            undo_actions.append(&redo_btn);
+            let history_list = gtk4::ListBox::new();
+            history_list.set_selection_mode(gtk4::SelectionMode::None);
+            history_list.set_css_classes(&["boxed-list"]);
+            let history_placeholder = Label::new(Some(&tr!("Nothing has been applied yet.")));
+            history_placeholder.set_margin_top(12);
+            history_placeholder.set_margin_bottom(12);
+            history_placeholder.set_css_classes(&["perf-card-subtitle"]);
+            history_list.set_placeholder(Some(&history_placeholder));
+            let history_scroll = ScrolledWindow::new();
+            history_scroll.set_hscrollbar_policy(gtk4::PolicyType::Never);
+            history_scroll.set_min_content_height(220);
+            history_scroll.set_min_content_width(380);
+            history_scroll.set_child(Some(&history_list));
+            let history_box = GtkBox::new(Orientation::Vertical, 6);
+            history_box.set_margin_top(6);
+            history_box.set_margin_bottom(6);
+            history_box.set_margin_start(6);
+            history_box.set_margin_end(6);
+            history_box.append(&undo_actions);
+            history_box.append(&history_scroll);
+            let history_popover = gtk4::Popover::new();
+            history_popover.set_child(Some(&history_box));
+            let history_btn = gtk4::MenuButton::new();
+            history_btn.set_label(&tr!("History"));
+            history_btn.set_css_classes(&["perf-action-secondary"]);
+            history_btn.set_popover(Some(&history_popover));
+
             actions.append(&service_btn);
             actions.append(&spacer);
+            actions.append(&history_btn);
             actions.append(&apply);
             perf_box.append(&actions);
 
@@ -540,7 +1205,6 @@ pub mod imp {
 
             // Command preview disclosure
             {
-                use std::cell::Cell;
                 let expanded = Cell::new(true);
                 let revealer = preview_revealer.clone();
                 let toggle_btn = preview_toggle.clone();
@@ -574,47 +1238,52 @@ pub mod imp {
             top_grid.set_row_homogeneous(false);
 
             // Shared gauge state for DrawingArea widgets
-            use std::cell::RefCell;
-            use std::collections::VecDeque;
-            use std::rc::Rc;
             use std::time::{Duration, Instant};
             #[derive(Clone, Copy, Default)]
             struct GaugeState {
                 vram_used_mib: f64,
                 vram_total_mib: f64,
                 fan_pct: f64,
+                gpu_temp: Option<f64>,
             }
             let gauge_state = Rc::new(RefCell::new(GaugeState::default()));
 
-            // History charts (kept in MHz). Time is seconds since chart start.
-            struct TimeSeriesState {
-                points: VecDeque<(f64, f64)>,
-            }
             let chart_start = Instant::now();
 
-            let core_clock_history: Rc<RefCell<TimeSeriesState>> = Rc::new(RefCell::new(TimeSeriesState {
-                points: {
-                    let mut v = VecDeque::with_capacity(300);
-                    // Assume x=0s and y=0MHz when plotting begins.
-                    v.push_back((0.0, 0.0));
-                    v
-                },
-            }));
-
-            let mem_clock_history: Rc<RefCell<TimeSeriesState>> = Rc::new(RefCell::new(TimeSeriesState {
-                points: {
-                    let mut v = VecDeque::with_capacity(300);
-                    v.push_back((0.0, 0.0));
-                    v
-                },
-            }));
+            // Every GPU is sampled all the time, so switching GPUs or opening
+            // the overview shows history right away.
+            let gpus = list_nvidia_gpus();
+            let gpu_indices: Vec<u32> = gpus.iter().filter_map(|(id, _)| id.parse().ok()).collect();
+            let histories: Histories = Rc::new(RefCell::new(gpu_indices.iter().map(|&index| (index, GpuHistory::new())).collect()));
+
+            // Marks an event on GPU `index`'s charts, as of now.
+            let mark_event: Rc<dyn Fn(u32, EventKind, String)> = {
+                let histories = histories.clone();
+                Rc::new(move |index, kind, detail| {
+                    if let Some(history) = histories.borrow_mut().get_mut(&index) {
+                        history.mark(kind, chart_start.elapsed().as_secs_f64(), detail);
+                    }
+                })
+            };
+            let notifier = Rc::new(Notifier::new(prefs.clone()));
+            {
+                let (tx, rx) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+                std::thread::spawn(move || watch_xid_errors(tx));
+                let mark_event = mark_event.clone();
+                let notifier = notifier.clone();
+                rx.attach(None, move |(index, detail): (u32, String)| {
+                    notifier.xid(index, &detail);
+                    mark_event(index, EventKind::Xid, detail);
+                    glib::Continue(true)
+                });
+            }
 
             // --- Left: VRAM card (circular gauge) ---
             let vram_card = GtkBox::new(Orientation::Vertical, 10);
             vram_card.set_css_classes(&["card", "metrics-card"]);
             vram_card.set_hexpand(true);
 
-            let vram_title = Label::new(Some("VRAM Usage"));
+            let vram_title = Label::new(Some(&tr!("VRAM Usage")));
             vram_title.set_halign(gtk4::Align::Start);
             vram_title.set_css_classes(&["metrics-title"]);
             vram_card.append(&vram_title);
@@ -629,7 +1298,7 @@ pub mod imp {
             let vram_center_used = Label::new(Some("0"));
             vram_center_used.set_css_classes(&["metrics-gauge-big"]);
             vram_center_used.set_halign(gtk4::Align::Center);
-            let vram_center_total = Label::new(Some("/ 0 MiB"));
+            let vram_center_total = Label::new(Some(&tr!("/ {} MiB", 0)));
             vram_center_total.set_css_classes(&["metrics-gauge-small"]);
             vram_center_total.set_halign(gtk4::Align::Center);
 
@@ -683,19 +1352,8 @@ pub mod imp {
             // Top-left
             top_grid.attach(&vram_card, 0, 0, 1, 1);
 
-            // --- Stats strip (goes into the chart header) ---
-            let stat_core_value = Label::new(Some("N/A"));
-            stat_core_value.set_halign(gtk4::Align::Start);
-            stat_core_value.set_css_classes(&["metrics-stat-value"]);
-            // Keep width stable as values update (prevents window width changes).
-            stat_core_value.set_width_chars(10);
-
-            let stat_mem_value = Label::new(Some("N/A"));
-            stat_mem_value.set_halign(gtk4::Align::Start);
-            stat_mem_value.set_css_classes(&["metrics-stat-value"]);
-            stat_mem_value.set_width_chars(10);
-
-            let stat_temp_value = Label::new(Some("N/A"));
+            // --- Stats strip (goes into the fan card footer) ---
+            let stat_temp_value = Label::new(Some(&tr!("N/A")));
             stat_temp_value.set_halign(gtk4::Align::Start);
             stat_temp_value.set_css_classes(&["metrics-stat-value"]);
             stat_temp_value.set_width_chars(6);
@@ -706,14 +1364,6 @@ pub mod imp {
                 b
             };
 
-            let stats_strip = GtkBox::new(Orientation::Horizontal, 18);
-            stats_strip.set_halign(gtk4::Align::End);
-            stats_strip.append(&mk_stat_value(&stat_core_value));
-
-            let mem_stats_strip = GtkBox::new(Orientation::Horizontal, 18);
-            mem_stats_strip.set_halign(gtk4::Align::End);
-            mem_stats_strip.append(&mk_stat_value(&stat_mem_value));
-
             let temp_stats_strip = GtkBox::new(Orientation::Horizontal, 18);
             temp_stats_strip.set_halign(gtk4::Align::End);
             temp_stats_strip.append(&mk_stat_value(&stat_temp_value));
@@ -723,7 +1373,7 @@ pub mod imp {
             usage_card.set_css_classes(&["card", "metrics-card", "metrics-card-snug"]);
             usage_card.set_hexpand(true);
 
-            let usage_title = Label::new(Some("GPU Usage"));
+            let usage_title = Label::new(Some(&tr!("GPU Usage")));
             usage_title.set_halign(gtk4::Align::Start);
             usage_title.set_css_classes(&["metrics-title"]);
             usage_card.append(&usage_title);
@@ -764,14 +1414,14 @@ pub mod imp {
             power_card.set_css_classes(&["card", "metrics-card", "metrics-card-snug"]);
             power_card.set_hexpand(true);
 
-            let power_title = Label::new(Some("Power Usage"));
+            let power_title = Label::new(Some(&tr!("Power Usage")));
             power_title.set_halign(gtk4::Align::Start);
             power_title.set_css_classes(&["metrics-title"]);
             power_card.append(&power_title);
 
             // Vertically center the power bar in the card like the GPU usage
             // bar, with the numeric value shown below.
-            let power_value = Label::new(Some("N/A"));
+            let power_value = Label::new(Some(&tr!("N/A")));
             power_value.set_halign(gtk4::Align::Center);
             power_value.set_css_classes(&["metrics-value"]);
             power_value.set_width_chars(18);
@@ -807,7 +1457,7 @@ pub mod imp {
             fan_card.set_css_classes(&["card", "metrics-card"]);
             fan_card.set_hexpand(true);
 
-            let fan_title = Label::new(Some("Fan Speed"));
+            let fan_title = Label::new(Some(&tr!("Fan Speed")));
             fan_title.set_halign(gtk4::Align::Start);
             fan_title.set_css_classes(&["metrics-title"]);
 
@@ -825,12 +1475,12 @@ pub mod imp {
             fan_gauge.set_hexpand(true);
             fan_gauge.set_vexpand(true);
 
-            let fan_center_pct = Label::new(Some("N/A"));
+            let fan_center_pct = Label::new(Some(&tr!("N/A")));
             fan_center_pct.set_halign(gtk4::Align::Center);
             fan_center_pct.set_css_classes(&["metrics-gauge-mid"]);
             fan_center_pct.set_width_chars(6);
 
-            let fan_center_rpm = Label::new(Some("N/A"));
+            let fan_center_rpm = Label::new(Some(&tr!("N/A")));
             fan_center_rpm.set_halign(gtk4::Align::Center);
             fan_center_rpm.set_css_classes(&["metrics-gauge-small"]);
             fan_center_rpm.set_width_chars(10);
@@ -896,380 +1546,638 @@ pub mod imp {
             power_card.set_valign(gtk4::Align::Fill);
             top_grid.attach(&power_card, 1, 1, 1, 1);
 
-            // --- Bottom: Memory clock history line chart ---
-            let mem_chart_card = GtkBox::new(Orientation::Vertical, 10);
-            mem_chart_card.set_css_classes(&["card", "metrics-card"]);
-            mem_chart_card.set_hexpand(true);
-            mem_chart_card.set_vexpand(true);
-            mem_chart_card.set_valign(gtk4::Align::Fill);
-
-            let mem_chart_title = Label::new(Some("Memory Clock History"));
-            mem_chart_title.set_halign(gtk4::Align::Start);
-            mem_chart_title.set_css_classes(&["metrics-title"]);
-
-            let mem_chart_header = GtkBox::new(Orientation::Horizontal, 12);
-            mem_chart_header.set_hexpand(true);
-            mem_chart_header.append(&mem_chart_title);
-            let mem_header_spacer = GtkBox::new(Orientation::Horizontal, 0);
-            mem_header_spacer.set_hexpand(true);
-            mem_chart_header.append(&mem_header_spacer);
-            mem_chart_card.append(&mem_chart_header);
-
-            let mem_chart = DrawingArea::new();
-            mem_chart.set_hexpand(true);
-            mem_chart.set_vexpand(true);
-            mem_chart.set_content_height(190);
-            // Keep the overall window from expanding; allow the chart to shrink.
-            mem_chart.set_content_width(250);
-            mem_chart_card.append(&mem_chart);
-
-            // Bottom-right: Memory clock value
-            let mem_chart_footer = GtkBox::new(Orientation::Horizontal, 0);
-            mem_chart_footer.set_hexpand(true);
-            let mem_footer_spacer = GtkBox::new(Orientation::Horizontal, 0);
-            mem_footer_spacer.set_hexpand(true);
-            mem_chart_footer.append(&mem_footer_spacer);
-            mem_chart_footer.append(&mem_stats_strip);
-            mem_chart_card.append(&mem_chart_footer);
-
+            // --- Bottom: history charts, one per metric ---
+            let chart_view = ChartView::new(chart_start, prefs.borrow().chart_window_secs);
+            chart_view.set_units(prefs.borrow().units);
+            chart_view.set_colors(prefs.borrow().chart_colors());
+
+            // How often the sampler reads NVML
+            let interval_ms = Some(prefs.borrow().sample_interval_ms).filter(|ms| sampler::INTERVALS.contains(ms)).unwrap_or(1000);
+            let sample_interval = Arc::new(AtomicU64::new(interval_ms));
+            let interval_combo = ComboBoxText::new();
+            for ms in sampler::INTERVALS {
+                let secs = number(ms as f64 / 1000.0, if ms % 1000 == 0 { 0 } else { 1 });
+                interval_combo.append(Some(&ms.to_string()), &tr!("Every {} s", secs));
+            }
+            interval_combo.set_active_id(Some(&interval_ms.to_string()));
+            interval_combo.set_valign(gtk4::Align::Center);
             {
-                let history = mem_clock_history.clone();
-                let start = chart_start;
-                mem_chart.set_draw_func(move |_, cr, w, h| {
-                    let w = w as f64;
-                    let h = h as f64;
-                    let pad = 12.0;
-                    let label_pad = 16.0;
-                    let plot_w = (w - 2.0 * pad).max(1.0);
-                    let plot_h = (h - 2.0 * pad).max(1.0);
-
-                    // X behavior:
-                    // - Fill left->right from x=0s.
-                    // - Start scrolling only after reaching 95% of the chart width.
-                    const WINDOW_SECS: f64 = 60.0;
-                    let fill_span = WINDOW_SECS * 0.95;
-                    let usable_w = plot_w * 0.95;
-                    let now_t = start.elapsed().as_secs_f64();
-                    let start_t = if now_t > fill_span { now_t - fill_span } else { 0.0 };
-
-                    // Axis label (Y only)
-                    cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
-                    cr.set_font_size(12.0);
-                    cr.set_source_rgba(1.0, 1.0, 1.0, 0.55);
-
-                    // Y-axis label (rotated, centered on left)
-                    cr.save().ok();
-                    cr.translate(10.0, h / 2.0);
-                    cr.rotate(-std::f64::consts::FRAC_PI_2);
-                    let y_label = "Memory Clock (MHz)";
-                    let (yext_w, yext_xb) = match cr.text_extents(y_label) {
-                        Ok(ext) => (ext.width(), ext.x_bearing()),
-                        Err(_) => (0.0, 0.0),
-                    };
-                    cr.move_to(-yext_w / 2.0 - yext_xb, -label_pad);
-                    let _ = cr.show_text(y_label);
-                    cr.restore().ok();
-
-                    let st = history.borrow();
-                    if st.points.is_empty() {
-                        return;
-                    }
-
-                    // Y axis anchored at 0 MHz.
-                    let min_v: f64 = 0.0;
-                    let mut max_v: f64 = 0.0;
-                    let end_t = start_t + fill_span;
-                    for &(_t, v) in st.points.iter() {
-                        if v.is_finite() {
-                            max_v = max_v.max(v);
-                        }
+                let sample_interval = sample_interval.clone();
+                interval_combo.connect_changed(move |combo| {
+                    if let Some(ms) = combo.active_id().and_then(|id| id.parse().ok()) {
+                        sample_interval.store(ms, Ordering::Relaxed);
                     }
-                    if max_v <= 0.0 {
-                        max_v = 1.0;
-                    } else {
-                        max_v += max_v * 0.08;
-                    }
-
-                    let denom = (max_v - min_v).max(1e-9_f64);
+                });
+            }
+            let charts: Vec<TimeChart> = Metric::ALL.into_iter().map(|m| TimeChart::new(m, histories.clone(), form_gpu.clone(), chart_view.clone())).collect();
 
-                    // Grid + Y ticks
-                    cr.set_line_width(1.0);
-                    for i in 0..=4 {
-                        let t = (i as f64) / 4.0;
-                        let y = pad + plot_h * t;
+            let charts_row = gtk4::FlowBox::new();
+            charts_row.set_selection_mode(gtk4::SelectionMode::None);
+            charts_row.set_homogeneous(true);
+            charts_row.set_min_children_per_line(1);
+            charts_row.set_max_children_per_line(2);
+            charts_row.set_column_spacing(12);
+            charts_row.set_row_spacing(12);
+            charts_row.set_hexpand(true);
 
-                        cr.set_source_rgba(1.0, 1.0, 1.0, 0.06);
-                        cr.move_to(pad, y);
-                        cr.line_to(pad + plot_w, y);
-                        let _ = cr.stroke();
-
-                        let value = max_v - (max_v - min_v) * t;
-                        cr.set_source_rgba(1.0, 1.0, 1.0, 0.45);
-                        cr.set_font_size(11.0);
-                        cr.move_to(pad + 4.0, y - 2.0);
-                        let _ = cr.show_text(&format!("{:.0}", value));
-                    }
+            // Let the user pick which charts appear; the clocks are shown by
+            // default.
+            let charts_menu = GtkBox::new(Orientation::Vertical, 4);
+            charts_menu.set_margin_top(6);
+            charts_menu.set_margin_bottom(6);
+            charts_menu.set_margin_start(6);
+            charts_menu.set_margin_end(6);
+            for chart in &charts {
+                charts_row.insert(&chart.card, -1);
+                let Some(child) = chart.card.parent() else {
+                    continue;
+                };
+                let visible = matches!(chart.metric(), Metric::CoreClock | Metric::MemClock);
+                child.set_visible(visible);
+
+                let check = gtk4::CheckButton::with_label(&chart.metric().title());
+                check.set_active(visible);
+                check.connect_toggled(move |c| child.set_visible(c.is_active()));
+                charts_menu.append(&check);
+            }
+            let charts_popover = gtk4::Popover::new();
+            charts_popover.set_child(Some(&charts_menu));
+            let charts_btn = gtk4::MenuButton::new();
+            charts_btn.set_label(&tr!("Charts"));
+            charts_btn.set_popover(Some(&charts_popover));
+
+            // Export the sampled history as CSV, or a chart as PNG.
+            let export_menu = GtkBox::new(Orientation::Vertical, 4);
+            export_menu.set_margin_top(6);
+            export_menu.set_margin_bottom(6);
+            export_menu.set_margin_start(6);
+            export_menu.set_margin_end(6);
+            let export_csv_btn = Button::with_label(&tr!("History as CSV…"));
+            export_csv_btn.add_css_class("flat");
+            let export_png_btn = Button::with_label(&tr!("Chart as PNG…"));
+            export_png_btn.add_css_class("flat");
+            export_menu.append(&export_csv_btn);
+            export_menu.append(&export_png_btn);
+            let export_popover = gtk4::Popover::new();
+            export_popover.set_child(Some(&export_menu));
+            let export_btn = gtk4::MenuButton::new();
+            export_btn.set_label(&tr!("Export"));
+            export_btn.set_popover(Some(&export_popover));
+            {
+                let window = window.clone();
+                let histories = histories.clone();
+                let chart_view = chart_view.clone();
+                let gpus = gpus.clone();
+                let popover = export_popover.clone();
+                export_csv_btn.connect_clicked(move |_| {
+                    popover.popdown();
+                    let histories = histories.clone();
+                    let chart_view = chart_view.clone();
+                    let identities = gpu_identities(&gpus);
+                    save_file_dialog(Some(&window), &tr!("Export Metrics History"), "zelos-metrics.csv", &[], move |path, _| {
+                        let mut file = std::io::BufWriter::new(std::fs::File::create(path).map_err(|e| e.to_string())?);
+                        super::chart::write_csv(&mut file, &histories.borrow(), &identities, &chart_view).map_err(|e| e.to_string())?;
+                        std::io::Write::flush(&mut file).map_err(|e| e.to_string())
+                    });
+                });
+            }
+            {
+                let window = window.clone();
+                let charts = charts.clone();
+                let popover = export_popover.clone();
+                export_png_btn.connect_clicked(move |_| {
+                    popover.popdown();
+                    let titles: Vec<(&str, String)> = charts.iter().map(|c| (c.metric().id(), c.metric().title())).collect();
+                    let chart_options: Vec<(&str, &str)> = titles.iter().map(|(id, title)| (*id, title.as_str())).collect();
+                    let resolutions: &[(&str, &str)] = &[("1280x720", "1280 × 720"), ("1920x1080", "1920 × 1080"), ("2560x1440", "2560 × 1440"), ("3840x2160", "3840 × 2160")];
+                    let charts = charts.clone();
+                    save_file_dialog(Some(&window), &tr!("Save Chart as PNG"), "zelos-chart.png", &[("chart", &tr!("Chart"), &chart_options), ("size", &tr!("Resolution"), resolutions)], move |path, dlg| {
+                        let metric = dlg.choice("chart").and_then(|id| Metric::from_id(&id)).unwrap_or(Metric::CoreClock);
+                        let (width, height) = dlg.choice("size").and_then(|s| s.split_once('x').and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))).unwrap_or((1920, 1080));
+                        let chart = charts.iter().find(|c| c.metric() == metric).ok_or("no such chart")?;
+                        chart.save_png(path, width, height)
+                    });
+                });
+            }
 
-                    // Axes at (0,0)
-                    cr.set_source_rgba(1.0, 1.0, 1.0, 0.12);
-                    cr.set_line_width(1.0);
-                    cr.move_to(pad, pad);
-                    cr.line_to(pad, pad + plot_h);
-                    let y0 = pad + plot_h;
-                    cr.move_to(pad, y0);
-                    cr.line_to(pad + usable_w, y0);
-                    let _ = cr.stroke();
+            metrics_layout.append(&top_grid);
+            metrics_layout.append(&charts_row);
 
-                    // Line
-                    cr.set_line_width(2.0);
-                    cr.set_line_cap(cairo::LineCap::Round);
-                    cr.set_line_join(cairo::LineJoin::Round);
-                    cr.set_source_rgba(0.18, 0.53, 1.0, 1.0);
+            // --- Overview: one compact card per GPU ---
+            #[derive(Clone)]
+            struct OverviewCard {
+                widget: GtkBox,
+                vram: Label,
+                core: Label,
+                temp: Label,
+                power: Label,
+                chart: DrawingArea,
+            }
 
-                    let last_y = st.points.back().map(|p| p.1).unwrap_or(0.0);
-                    let mut started = false;
-                    for &(t, v) in st.points.iter() {
-                        if t < start_t {
-                            continue;
-                        }
-                        if t > end_t {
-                            break;
-                        }
-                        let x = pad + ((t - start_t) / fill_span).clamp(0.0, 1.0) * usable_w;
-                        let y = pad + (1.0 - ((v - min_v) / denom).clamp(0.0, 1.0)) * plot_h;
-                        if !started {
-                            cr.move_to(x, y);
-                            started = true;
-                        } else {
-                            cr.line_to(x, y);
+            let overview_flow = gtk4::FlowBox::new();
+            overview_flow.set_selection_mode(gtk4::SelectionMode::None);
+            overview_flow.set_homogeneous(true);
+            overview_flow.set_min_children_per_line(1);
+            overview_flow.set_max_children_per_line(3);
+            overview_flow.set_column_spacing(12);
+            overview_flow.set_row_spacing(12);
+            overview_flow.set_valign(gtk4::Align::Start);
+
+            let mut overview_cards: Vec<(u32, OverviewCard)> = Vec::new();
+            for (id, label) in &gpus {
+                let Ok(index) = id.parse::<u32>() else {
+                    continue;
+                };
+                let card = GtkBox::new(Orientation::Vertical, 8);
+                card.set_css_classes(&["card", "metrics-card", "overview-card"]);
+
+                let title = Label::new(Some(label));
+                title.set_halign(gtk4::Align::Start);
+                title.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+                title.set_css_classes(&["metrics-title"]);
+                card.append(&title);
+
+                let readouts = Grid::new();
+                readouts.set_column_spacing(12);
+                readouts.set_row_spacing(4);
+                let mk_readout = |row: i32, name: &str| -> Label {
+                    let name_lbl = Label::new(Some(name));
+                    name_lbl.set_halign(gtk4::Align::Start);
+                    name_lbl.set_css_classes(&["metrics-stat-name"]);
+                    let value = Label::new(Some(&tr!("N/A")));
+                    value.set_halign(gtk4::Align::End);
+                    value.set_hexpand(true);
+                    value.set_css_classes(&["metrics-stat-value"]);
+                    readouts.attach(&name_lbl, 0, row, 1, 1);
+                    readouts.attach(&value, 1, row, 1, 1);
+                    value
+                };
+                let vram = mk_readout(0, &tr!("VRAM"));
+                let core = mk_readout(1, &tr!("Core Clock"));
+                let temp = mk_readout(2, &tr!("Temperature"));
+                let power = mk_readout(3, &tr!("Power"));
+                card.append(&readouts);
+
+                let chart = DrawingArea::new();
+                chart.set_hexpand(true);
+                chart.set_content_width(200);
+                chart.set_content_height(60);
+                {
+                    let histories = histories.clone();
+                    let start = chart_start;
+                    chart.set_draw_func(move |_, cr, w, h| {
+                        if let Some(series) = histories.borrow().get(&index).and_then(|h| h.series(Metric::CoreClock)) {
+                            draw_sparkline(cr, w as f64, h as f64, &series.points, start.elapsed().as_secs_f64(), SPARKLINE_SECS);
                         }
-                    }
+                    });
+                }
+                card.append(&chart);
 
-                    let t_now = now_t.min(end_t);
-                    if t_now >= start_t {
-                        let x = pad + ((t_now - start_t) / fill_span).clamp(0.0, 1.0) * usable_w;
-                        let y = pad + (1.0 - ((last_y - min_v) / denom).clamp(0.0, 1.0)) * plot_h;
-                        if !started {
-                            cr.move_to(x, y);
-                        } else {
-                            cr.line_to(x, y);
-                        }
-                    }
-                    let _ = cr.stroke();
-                });
+                overview_flow.insert(&card, -1);
+                overview_cards.push((index, OverviewCard { widget: card, vram, core, temp, power, chart }));
             }
 
-            // --- Bottom: Core clock history line chart ---
-            let core_chart_card = GtkBox::new(Orientation::Vertical, 10);
-            core_chart_card.set_css_classes(&["card", "metrics-card"]);
-            core_chart_card.set_hexpand(true);
-            core_chart_card.set_vexpand(true);
-            core_chart_card.set_valign(gtk4::Align::Fill);
-
-            let core_chart_title = Label::new(Some("Core Clock History"));
-            core_chart_title.set_halign(gtk4::Align::Start);
-            core_chart_title.set_css_classes(&["metrics-title"]);
-
-            let chart_header = GtkBox::new(Orientation::Horizontal, 12);
-            chart_header.set_hexpand(true);
-            chart_header.append(&core_chart_title);
-            let header_spacer = GtkBox::new(Orientation::Horizontal, 0);
-            header_spacer.set_hexpand(true);
-            chart_header.append(&header_spacer);
-            core_chart_card.append(&chart_header);
-
-            let core_chart = DrawingArea::new();
-            core_chart.set_hexpand(true);
-            core_chart.set_vexpand(true);
-            core_chart.set_content_height(190);
-            // Keep the overall window from expanding; allow the chart to shrink.
-            core_chart.set_content_width(250);
-            core_chart_card.append(&core_chart);
-
-            // Bottom-right: Core clock value
-            let core_chart_footer = GtkBox::new(Orientation::Horizontal, 0);
-            core_chart_footer.set_hexpand(true);
-            let core_footer_spacer = GtkBox::new(Orientation::Horizontal, 0);
-            core_footer_spacer.set_hexpand(true);
-            core_chart_footer.append(&core_footer_spacer);
-            core_chart_footer.append(&stats_strip);
-            core_chart_card.append(&core_chart_footer);
-
+            let overview_scroll = ScrolledWindow::new();
+            overview_scroll.set_hscrollbar_policy(gtk4::PolicyType::Never);
+            overview_scroll.set_hexpand(true);
+            overview_scroll.set_vexpand(true);
+            overview_scroll.set_child(Some(&overview_flow));
+
+            // Switch between the selected GPU's details and the overview.
+            let metrics_stack = gtk4::Stack::new();
+            metrics_stack.set_hexpand(true);
+            metrics_stack.set_vexpand(true);
+            metrics_stack.add_named(&metrics_layout, Some("detail"));
+            metrics_stack.add_named(&overview_scroll, Some("overview"));
+
+            let metrics_gpu_title = Label::new(None);
+            metrics_gpu_title.set_halign(gtk4::Align::Start);
+            metrics_gpu_title.set_hexpand(true);
+            metrics_gpu_title.set_css_classes(&["perf-section-title"]);
             {
-                let history = core_clock_history.clone();
-                let start = chart_start;
-                core_chart.set_draw_func(move |_, cr, w, h| {
-                    let w = w as f64;
-                    let h = h as f64;
-                    let pad = 12.0;
-                    let label_pad = 16.0;
-                    let plot_w = (w - 2.0 * pad).max(1.0);
-                    let plot_h = (h - 2.0 * pad).max(1.0);
-
-                    // X behavior:
-                    // - Fill left->right from x=0s.
-                    // - Start scrolling only after reaching 95% of the chart width.
-                    const WINDOW_SECS: f64 = 60.0;
-                    let fill_span = WINDOW_SECS * 0.95;
-                    let usable_w = plot_w * 0.95;
-                    let now_t = start.elapsed().as_secs_f64();
-                    let start_t = if now_t > fill_span { now_t - fill_span } else { 0.0 };
-
-                    // Axis label (Y only)
-                    cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
-                    cr.set_font_size(12.0);
-                    cr.set_source_rgba(1.0, 1.0, 1.0, 0.55);
-
-                    // Y-axis label (rotated, centered on left)
-                    cr.save().ok();
-                    cr.translate(10.0, h / 2.0);
-                    cr.rotate(-std::f64::consts::FRAC_PI_2);
-                    let y_label = "Core Clock (MHz)";
-                    let (yext_w, yext_xb) = match cr.text_extents(y_label) {
-                        Ok(ext) => (ext.width(), ext.x_bearing()),
-                        Err(_) => (0.0, 0.0),
-                    };
-                    cr.move_to(-yext_w / 2.0 - yext_xb, -label_pad);
-                    let _ = cr.show_text(y_label);
-                    cr.restore().ok();
+                let update_title = {
+                    let metrics_gpu_title = metrics_gpu_title.clone();
+                    move |combo: &ComboBoxText| metrics_gpu_title.set_text(&combo.active_text().map(|t| t.to_string()).unwrap_or_default())
+                };
+                update_title(&gpu_combo);
+                gpu_combo.connect_changed(update_title);
+            }
 
-                    let st = history.borrow();
-                    if st.points.is_empty() {
-                        return;
+            let detail_btn = gtk4::ToggleButton::with_label(&tr!("Selected GPU"));
+            let overview_btn = gtk4::ToggleButton::with_label(&tr!("All GPUs"));
+            overview_btn.set_group(Some(&detail_btn));
+            detail_btn.set_active(true);
+            let mode_box = GtkBox::new(Orientation::Horizontal, 0);
+            mode_box.set_css_classes(&["linked"]);
+            mode_box.append(&detail_btn);
+            mode_box.append(&overview_btn);
+            {
+                let metrics_stack = metrics_stack.clone();
+                let metrics_gpu_title = metrics_gpu_title.clone();
+                detail_btn.connect_toggled(move |b| {
+                    if b.is_active() {
+                        metrics_stack.set_visible_child_name("detail");
+                        metrics_gpu_title.set_visible(true);
                     }
-
-                    // Y axis is always anchored at 0 MHz so the axes intersection is (0,0).
-                    // (i.e., the chart never shifts below 0 even if we add headroom.)
-                    let min_v: f64 = 0.0;
-                    let mut max_v: f64 = 0.0;
-                    let end_t = start_t + fill_span;
-                    for &(_t, v) in st.points.iter() {
-                        if v.is_finite() {
-                            max_v = max_v.max(v);
-                        }
+                });
+            }
+            {
+                let metrics_stack = metrics_stack.clone();
+                let metrics_gpu_title = metrics_gpu_title.clone();
+                overview_btn.connect_toggled(move |b| {
+                    if b.is_active() {
+                        metrics_stack.set_visible_child_name("overview");
+                        metrics_gpu_title.set_visible(false);
                     }
+                });
+            }
+
+            // Clicking a card opens that GPU's details.
+            for (index, card) in &overview_cards {
+                let click = gtk4::GestureClick::new();
+                let gpu_combo = gpu_combo.clone();
+                let detail_btn = detail_btn.clone();
+                let index = *index;
+                click.connect_released(move |_, _, _, _| {
+                    gpu_combo.set_active_id(Some(&index.to_string()));
+                    detail_btn.set_active(true);
+                });
+                card.widget.add_controller(click);
+            }
 
-                    if max_v <= 0.0 {
-                        max_v = 1.0;
+            let metrics_header = GtkBox::new(Orientation::Horizontal, 12);
+            metrics_header.append(&metrics_gpu_title);
+            let metrics_header_spacer = GtkBox::new(Orientation::Horizontal, 0);
+            metrics_header_spacer.set_hexpand(true);
+            metrics_header.append(&metrics_header_spacer);
+            metrics_header.append(&interval_combo);
+            metrics_header.append(&chart_view.controls);
+            metrics_header.append(&charts_btn);
+            metrics_header.append(&export_btn);
+            metrics_header.append(&mode_box);
+
+            metrics_box.append(&metrics_header);
+            metrics_box.append(&metrics_stack);
+
+            // Redraw the visible charts at ~30fps so they feel live even though samples are 1Hz.
+            {
+                let charts = charts.clone();
+                let metrics_stack = metrics_stack.clone();
+                let overview_charts: Vec<DrawingArea> = overview_cards.iter().map(|(_, card)| card.chart.clone()).collect();
+                glib::timeout_add_local(Duration::from_millis(33), move || {
+                    if metrics_stack.visible_child_name().as_deref() == Some("overview") {
+                        for chart in &overview_charts {
+                            chart.queue_draw();
+                        }
                     } else {
-                        max_v += max_v * 0.08;
+                        for chart in &charts {
+                            chart.queue_draw();
+                        }
                     }
+                    glib::Continue(true)
+                });
+            }
 
-                    let denom = (max_v - min_v).max(1e-9_f64);
+            // Fan tab ---------------------------------------------------
+            let fan_box = GtkBox::new(Orientation::Vertical, 14);
+            fan_box.set_margin_top(16);
+            fan_box.set_margin_bottom(16);
+            fan_box.set_margin_start(16);
+            fan_box.set_margin_end(16);
 
-                    // Subtle grid + Y tick labels for each grid line
+            let fan_title = Label::new(Some(&tr!("Fan Curve")));
+            fan_title.set_halign(gtk4::Align::Start);
+            fan_title.set_css_classes(&["perf-section-title"]);
+            let fan_sub = Label::new(Some(&tr!("Drag the points to set the fan speed for each temperature. Custom curves are driven by `zelos daemon`.")));
+            fan_sub.set_halign(gtk4::Align::Start);
+            fan_sub.set_wrap(true);
+            fan_sub.set_xalign(0.0);
+            fan_sub.set_css_classes(&["perf-card-subtitle"]);
+            fan_box.append(&fan_title);
+            fan_box.append(&fan_sub);
+
+            let fan_card = GtkBox::new(Orientation::Vertical, 10);
+            fan_card.set_css_classes(&["card", "perf-card"]);
+
+            let fan_mode_row = GtkBox::new(Orientation::Horizontal, 12);
+            fan_mode_row.set_css_classes(&["perf-row"]);
+            let fan_mode_texts = GtkBox::new(Orientation::Vertical, 2);
+            fan_mode_texts.set_hexpand(true);
+            let fan_mode_title = Label::new(Some(&tr!("Custom Curve")));
+            fan_mode_title.set_halign(gtk4::Align::Start);
+            fan_mode_title.set_css_classes(&["perf-row-title"]);
+            let fan_mode_sub = Label::new(Some(&tr!("When off, the driver controls the fans")));
+            fan_mode_sub.set_halign(gtk4::Align::Start);
+            fan_mode_sub.set_css_classes(&["perf-card-subtitle"]);
+            fan_mode_texts.append(&fan_mode_title);
+            fan_mode_texts.append(&fan_mode_sub);
+            let fan_custom_switch = gtk4::Switch::new();
+            fan_custom_switch.set_valign(gtk4::Align::Center);
+            fan_mode_row.append(&fan_mode_texts);
+            fan_mode_row.append(&fan_custom_switch);
+            fan_card.append(&fan_mode_row);
+            fan_card.append(&Separator::new(Orientation::Horizontal));
+
+            let fan_presets_row = GtkBox::new(Orientation::Horizontal, 12);
+            fan_presets_row.set_css_classes(&["perf-row"]);
+            let fan_presets_title = Label::new(Some(&tr!("Presets")));
+            fan_presets_title.set_halign(gtk4::Align::Start);
+            fan_presets_title.set_hexpand(true);
+            fan_presets_title.set_css_classes(&["perf-row-title"]);
+            let fan_presets_box = GtkBox::new(Orientation::Horizontal, 0);
+            fan_presets_box.set_css_classes(&["linked"]);
+            fan_presets_row.append(&fan_presets_title);
+            fan_presets_row.append(&fan_presets_box);
+            fan_card.append(&fan_presets_row);
+
+            let fan_area = DrawingArea::new();
+            fan_area.set_hexpand(true);
+            fan_area.set_content_width(480);
+            fan_area.set_content_height(260);
+            fan_card.append(&fan_area);
+            fan_box.append(&fan_card);
+
+            let fan_save_btn = Button::with_label(&tr!("Save Fan Curve"));
+            fan_save_btn.set_css_classes(&["suggested-action", "perf-action-primary"]);
+            fan_save_btn.set_halign(gtk4::Align::End);
+            fan_box.append(&fan_save_btn);
+
+            // The curve being edited for the selected GPU.
+            let fan_curve = Rc::new(RefCell::new(FanCurve::default()));
+            {
+                let fan_curve = fan_curve.clone();
+                let gauge_state = gauge_state.clone();
+                fan_area.set_draw_func(move |_, cr, w, h| {
+                    let plot = FanPlot::new(w as f64, h as f64);
+                    let curve = fan_curve.borrow();
+                    // Dim the curve while the driver is in control.
+                    let alpha = if curve.custom { 1.0 } else { 0.4 };
+
+                    cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+                    cr.set_font_size(11.0);
                     cr.set_line_width(1.0);
                     for i in 0..=4 {
-                        let t = (i as f64) / 4.0;
-                        let y = pad + plot_h * t;
-
-                        // Grid line
+                        let speed = i as f64 * 25.0;
+                        let (x0, y) = plot.point_to_xy(FanPlot::T_MIN, speed);
+                        let (x1, _) = plot.point_to_xy(FanPlot::T_MAX, speed);
                         cr.set_source_rgba(1.0, 1.0, 1.0, 0.06);
-                        cr.move_to(pad, y);
-                        cr.line_to(pad + plot_w, y);
+                        cr.move_to(x0, y);
+                        cr.line_to(x1, y);
                         let _ = cr.stroke();
-
-                        // Tick label (value at this grid line)
-                        let value = max_v - (max_v - min_v) * t;
                         cr.set_source_rgba(1.0, 1.0, 1.0, 0.45);
-                        cr.set_font_size(11.0);
-                        cr.move_to(pad + 4.0, y - 2.0);
-                        let _ = cr.show_text(&format!("{:.0}", value));
+                        cr.move_to(4.0, y + 4.0);
+                        let _ = cr.show_text(&format!("{}%", number(speed, 0)));
+                    }
+                    for temp in (20..=100).step_by(20) {
+                        let (x, y0) = plot.point_to_xy(temp as f64, 100.0);
+                        let (_, y1) = plot.point_to_xy(temp as f64, 0.0);
+                        cr.set_source_rgba(1.0, 1.0, 1.0, 0.06);
+                        cr.move_to(x, y0);
+                        cr.line_to(x, y1);
+                        let _ = cr.stroke();
+                        cr.set_source_rgba(1.0, 1.0, 1.0, 0.45);
+                        cr.move_to(x - 12.0, y1 + 18.0);
+                        let _ = cr.show_text(&format!("{}°C", temp));
                     }
 
-                    // Axes (origin at bottom-left of plot area is always (0,0))
-                    cr.set_source_rgba(1.0, 1.0, 1.0, 0.12);
-                    cr.set_line_width(1.0);
-                    // Y axis
-                    cr.move_to(pad, pad);
-                    cr.line_to(pad, pad + plot_h);
-                    // X axis (at 0 MHz)
-                    let y0 = pad + plot_h;
-                    cr.move_to(pad, y0);
-                    cr.line_to(pad + usable_w, y0);
-                    let _ = cr.stroke();
-
-                    // Line
-                    cr.set_line_width(2.0);
-                    cr.set_line_cap(cairo::LineCap::Round);
-                    cr.set_line_join(cairo::LineJoin::Round);
-                    // Orange hue that remains readable in light and dark.
-                    cr.set_source_rgba(0.95, 0.55, 0.20, 1.0);
-
-                    // Build a visible polyline.
-                    let last_y = st.points.back().map(|p| p.1).unwrap_or(0.0);
-
-                    let mut started = false;
-                    for &(t, v) in st.points.iter() {
-                        if t < start_t {
-                            continue;
-                        }
-                        if t > end_t {
-                            break;
-                        }
-                        let x = pad + ((t - start_t) / fill_span).clamp(0.0, 1.0) * usable_w;
-                        let y = pad + (1.0 - ((v - min_v) / denom).clamp(0.0, 1.0)) * plot_h;
-                        if !started {
-                            cr.move_to(x, y);
-                            started = true;
-                        } else {
+                    // The curve, held flat beyond its first and last point.
+                    if let (Some(first), Some(last)) = (curve.points.first(), curve.points.last()) {
+                        cr.set_line_width(2.0);
+                        cr.set_line_join(cairo::LineJoin::Round);
+                        cr.set_source_rgba(0.18, 0.53, 1.0, alpha);
+                        let (x, y) = plot.point_to_xy(FanPlot::T_MIN, first.speed as f64);
+                        cr.move_to(x, y);
+                        for p in &curve.points {
+                            let (x, y) = plot.point_to_xy(p.temp as f64, p.speed as f64);
                             cr.line_to(x, y);
                         }
-                    }
+                        let (x, y) = plot.point_to_xy(FanPlot::T_MAX, last.speed as f64);
+                        cr.line_to(x, y);
+                        let _ = cr.stroke();
 
-                    // Extend to "now" so the line visually grows between 1Hz samples.
-                    let t_now = now_t.min(end_t);
-                    if t_now >= start_t {
-                        let x = pad + ((t_now - start_t) / fill_span).clamp(0.0, 1.0) * usable_w;
-                        let y = pad + (1.0 - ((last_y - min_v) / denom).clamp(0.0, 1.0)) * plot_h;
-                        if !started {
-                            cr.move_to(x, y);
-                        } else {
-                            cr.line_to(x, y);
+                        for p in &curve.points {
+                            let (x, y) = plot.point_to_xy(p.temp as f64, p.speed as f64);
+                            cr.arc(x, y, 6.0, 0.0, std::f64::consts::TAU);
+                            let _ = cr.fill();
                         }
                     }
-                    let _ = cr.stroke();
+
+                    // Live marker: current temperature and fan speed.
+                    let st = *gauge_state.borrow();
+                    if let Some(temp) = st.gpu_temp {
+                        let (x, y) = plot.point_to_xy(temp, st.fan_pct);
+                        let (_, y_top) = plot.point_to_xy(temp, 100.0);
+                        let (_, y_bottom) = plot.point_to_xy(temp, 0.0);
+                        cr.set_source_rgba(0.95, 0.55, 0.20, 0.5);
+                        cr.set_line_width(1.0);
+                        cr.set_dash(&[4.0, 4.0], 0.0);
+                        cr.move_to(x, y_top);
+                        cr.line_to(x, y_bottom);
+                        let _ = cr.stroke();
+                        cr.set_dash(&[], 0.0);
+                        cr.set_source_rgba(0.95, 0.55, 0.20, 1.0);
+                        cr.arc(x, y, 5.0, 0.0, std::f64::consts::TAU);
+                        let _ = cr.fill();
+                        cr.move_to(x + 8.0, y - 8.0);
+                        let _ = cr.show_text(&format!("{} °C · {}%", number(temp, 0), number(st.fan_pct, 0)));
+                    }
                 });
             }
 
-            // Redraw both charts at ~30fps so they feel live even though samples are 1Hz.
+            // Drag points; each stays between its neighbours' temperatures.
             {
-                let core = core_chart.clone();
-                let mem = mem_chart.clone();
-                glib::timeout_add_local(Duration::from_millis(33), move || {
-                    core.queue_draw();
-                    mem.queue_draw();
-                    glib::Continue(true)
+                let drag = gtk4::GestureDrag::new();
+                let dragging: Rc<Cell<Option<(usize, f64, f64)>>> = Rc::new(Cell::new(None));
+                {
+                    let fan_curve = fan_curve.clone();
+                    let fan_area = fan_area.clone();
+                    let dragging = dragging.clone();
+                    drag.connect_drag_begin(move |_, x, y| {
+                        let plot = FanPlot::new(fan_area.width() as f64, fan_area.height() as f64);
+                        let nearest = fan_curve
+                            .borrow()
+                            .points
+                            .iter()
+                            .enumerate()
+                            .map(|(i, p)| {
+                                let (px, py) = plot.point_to_xy(p.temp as f64, p.speed as f64);
+                                (i, px, py, (px - x).hypot(py - y))
+                            })
+                            .filter(|&(_, _, _, d)| d <= 14.0)
+                            .min_by(|a, b| a.3.total_cmp(&b.3));
+                        dragging.set(nearest.map(|(i, px, py, _)| (i, px, py)));
+                    });
+                }
+                {
+                    let fan_curve = fan_curve.clone();
+                    let fan_area = fan_area.clone();
+                    let dragging = dragging.clone();
+                    drag.connect_drag_update(move |_, dx, dy| {
+                        let Some((i, x0, y0)) = dragging.get() else {
+                            return;
+                        };
+                        let plot = FanPlot::new(fan_area.width() as f64, fan_area.height() as f64);
+                        let (temp, speed) = plot.xy_to_point(x0 + dx, y0 + dy);
+                        let mut curve = fan_curve.borrow_mut();
+                        let lo = if i > 0 { curve.points[i - 1].temp as f64 + 1.0 } else { FanPlot::T_MIN };
+                        let hi = curve.points.get(i + 1).map(|p| p.temp as f64 - 1.0).unwrap_or(FanPlot::T_MAX);
+                        curve.points[i] = FanPoint { temp: temp.clamp(lo, hi.max(lo)).round() as u32, speed: speed.clamp(0.0, 100.0).round() as u32 };
+                        drop(curve);
+                        fan_area.queue_draw();
+                    });
+                }
+                drag.connect_drag_end(move |_, _, _| dragging.set(None));
+                fan_area.add_controller(drag);
+            }
+
+            let preset_names = [tr!("Quiet"), tr!("Balanced"), tr!("Aggressive")];
+            for ((_, points), name) in FAN_PRESETS.into_iter().zip(preset_names) {
+                let btn = Button::with_label(&name);
+                let fan_curve = fan_curve.clone();
+                let fan_area = fan_area.clone();
+                btn.connect_clicked(move |_| {
+                    fan_curve.borrow_mut().points = fan_preset(points);
+                    fan_area.queue_draw();
+                });
+                fan_presets_box.append(&btn);
+            }
+            {
+                let fan_curve = fan_curve.clone();
+                let fan_area = fan_area.clone();
+                fan_custom_switch.connect_active_notify(move |sw| {
+                    fan_curve.borrow_mut().custom = sw.is_active();
+                    fan_area.queue_draw();
                 });
             }
 
-            let charts_row = Grid::new();
-            charts_row.set_column_spacing(12);
-            charts_row.set_row_spacing(12);
-            charts_row.set_hexpand(true);
-            charts_row.set_column_homogeneous(true);
-            charts_row.attach(&core_chart_card, 0, 0, 1, 1);
-            charts_row.attach(&mem_chart_card, 1, 0, 1, 1);
+            // Load the selected GPU's saved curve, starting from the
+            // balanced preset if it has none.
+            let load_fan_curve = {
+                let fan_curve = fan_curve.clone();
+                let fan_area = fan_area.clone();
+                let fan_custom_switch = fan_custom_switch.clone();
+                let saved_config = saved_config.clone();
+                move |index: u32| {
+                    let mut curve = saved_config.borrow().fan_curves.get(&index).cloned().unwrap_or_default();
+                    if curve.points.is_empty() {
+                        curve.points = fan_preset(FAN_PRESETS[1].1);
+                    }
+                    let custom = curve.custom;
+                    *fan_curve.borrow_mut() = curve;
+                    fan_custom_switch.set_active(custom);
+                    fan_area.queue_draw();
+                }
+            };
+            load_fan_curve(gpu_index_num);
+            gpu_combo.connect_changed(move |combo| {
+                if let Some(index) = combo.active_id().and_then(|id| id.parse::<u32>().ok()) {
+                    load_fan_curve(index);
+                }
+            });
+            {
+                let window = window.clone();
+                let config_path = config_path.clone();
+                let saved_config = saved_config.clone();
+                let form_gpu = form_gpu.clone();
+                let fan_curve = fan_curve.clone();
+                let activity = activity.clone();
+                fan_save_btn.connect_clicked(move |_| {
+                    let mut config = saved_config.borrow().clone();
+                    config.fan_curves.insert(form_gpu.get(), fan_curve.borrow().clone());
+                    match save_config(&config_path, &config, false, &activity) {
+                        Ok(()) => {
+                            *saved_config.borrow_mut() = config;
+                            show_message(Some(&window), MessageType::Info, ButtonsType::Ok, &tr!("Fan curve saved. A running `zelos daemon` picks it up right away."));
+                        }
+                        Err(msg) => show_message(Some(&window), MessageType::Error, ButtonsType::Ok, &tr!("Failed to save the fan curve: {}", msg)),
+                    }
+                });
+            }
 
-            metrics_layout.append(&top_grid);
-            metrics_layout.append(&charts_row);
+            // Profiles tab ----------------------------------------------
+            let profiles_box = GtkBox::new(Orientation::Vertical, 14);
+            profiles_box.set_margin_top(16);
+            profiles_box.set_margin_bottom(16);
+            profiles_box.set_margin_start(16);
+            profiles_box.set_margin_end(16);
+
+            let profiles_title = Label::new(Some(&tr!("Profiles")));
+            profiles_title.set_halign(gtk4::Align::Start);
+            profiles_title.set_css_classes(&["perf-section-title"]);
+            let profiles_sub = Label::new(Some(&tr!("Named settings for the selected GPU, saved in the config file so the CLI and boot job can use them.")));
+            profiles_sub.set_halign(gtk4::Align::Start);
+            profiles_sub.set_wrap(true);
+            profiles_sub.set_xalign(0.0);
+            profiles_sub.set_css_classes(&["perf-card-subtitle"]);
+            profiles_box.append(&profiles_title);
+            profiles_box.append(&profiles_sub);
+
+            let profiles_list = gtk4::ListBox::new();
+            profiles_list.set_selection_mode(gtk4::SelectionMode::Single);
+            profiles_list.set_css_classes(&["boxed-list"]);
+            let profiles_placeholder = Label::new(Some(&tr!("No profiles yet. Save the current settings to create one.")));
+            profiles_placeholder.set_margin_top(18);
+            profiles_placeholder.set_margin_bottom(18);
+            profiles_placeholder.set_css_classes(&["perf-card-subtitle"]);
+            profiles_list.set_placeholder(Some(&profiles_placeholder));
+
+            let profiles_scroll = ScrolledWindow::new();
+            profiles_scroll.set_hscrollbar_policy(gtk4::PolicyType::Never);
+            profiles_scroll.set_vexpand(true);
+            profiles_scroll.set_child(Some(&profiles_list));
+            profiles_box.append(&profiles_scroll);
+
+            let profile_save_btn = Button::with_label(&tr!("Save Current…"));
+            profile_save_btn.set_css_classes(&["perf-action-secondary"]);
+            let profile_rename_btn = Button::with_label(&tr!("Rename…"));
+            profile_rename_btn.set_css_classes(&["perf-action-secondary"]);
+            let profile_duplicate_btn = Button::with_label(&tr!("Duplicate"));
+            profile_duplicate_btn.set_css_classes(&["perf-action-secondary"]);
+            let profile_delete_btn = Button::with_label(&tr!("Delete"));
+            profile_delete_btn.set_css_classes(&["destructive-action", "perf-action-secondary"]);
+            let profile_apply_btn = Button::with_label(&tr!("Apply Profile"));
+            profile_apply_btn.set_css_classes(&["suggested-action", "perf-action-primary"]);
+            for btn in [&profile_rename_btn, &profile_duplicate_btn, &profile_delete_btn, &profile_apply_btn] {
+                btn.set_sensitive(false);
+            }
 
-            metrics_box.append(&metrics_layout);
+            let profile_actions = GtkBox::new(Orientation::Horizontal, 12);
+            profile_actions.append(&profile_save_btn);
+            profile_actions.append(&profile_rename_btn);
+            profile_actions.append(&profile_duplicate_btn);
+            profile_actions.append(&profile_delete_btn);
+            let profile_actions_spacer = GtkBox::new(Orientation::Horizontal, 0);
+            profile_actions_spacer.set_hexpand(true);
+            profile_actions.append(&profile_actions_spacer);
+            profile_actions.append(&profile_apply_btn);
+            profiles_box.append(&profile_actions);
 
             // Libadwaita-style tabs (ViewStack + ViewSwitcherTitle)
             let stack = adw::ViewStack::new();
-            let perf_page = stack.add_titled(&perf_box, Some("performance"), "Performance");
+            let perf_page = stack.add_titled(&perf_box, Some("performance"), &tr!("Performance"));
             perf_page.set_icon_name(Some("preferences-system-symbolic"));
 
-            let metrics_page = stack.add_titled(&metrics_box, Some("metrics"), "Metrics");
+            let metrics_page = stack.add_titled(&metrics_box, Some("metrics"), &tr!("Metrics"));
             metrics_page.set_icon_name(Some("utilities-system-monitor-symbolic"));
 
+            let fan_page = stack.add_titled(&fan_box, Some("fan"), &tr!("Fan"));
+            fan_page.set_icon_name(Some("weather-windy-symbolic"));
+
+            let profiles_page = stack.add_titled(&profiles_box, Some("profiles"), &tr!("Profiles"));
+            profiles_page.set_icon_name(Some("view-list-symbolic"));
+
             let switcher = adw::ViewSwitcherTitle::new();
             switcher.set_stack(Some(&stack));
 
             let header = adw::HeaderBar::new();
             header.set_title_widget(Some(&switcher));
 
+            // Active profile of the selected GPU.
+            let profile_label = Label::new(None);
+            profile_label.set_css_classes(&["perf-card-subtitle"]);
+            profile_label.set_visible(false);
+            header.pack_start(&profile_label);
+
             // Explicit window controls on the right.
             header.set_show_end_title_buttons(false);
             header.set_show_start_title_buttons(false);
@@ -1277,19 +2185,117 @@ pub mod imp {
             controls.set_decoration_layout(Some("minimize,maximize,close"));
             header.pack_end(&controls);
 
+            let prefs_btn = Button::from_icon_name("preferences-system-symbolic");
+            prefs_btn.set_tooltip_text(Some(&tr!("Preferences")));
+            {
+                let window = window.clone();
+                let prefs = prefs.clone();
+                let prefs_for_change = prefs.clone();
+                let chart_view = chart_view.clone();
+                let charts = charts.clone();
+                // Units and colors apply to the charts at once; readouts
+                // follow with the next sample.
+                let on_change: Rc<dyn Fn()> = Rc::new(move || {
+                    chart_view.set_units(prefs_for_change.borrow().units);
+                    chart_view.set_colors(prefs_for_change.borrow().chart_colors());
+                    for chart in &charts {
+                        chart.refresh();
+                        chart.queue_draw();
+                    }
+                });
+                prefs_btn.connect_clicked(move |_| prefs::show_window(&window, prefs.clone(), on_change.clone()));
+            }
+            header.pack_end(&prefs_btn);
+
+            // Compact readouts of the selected GPU in a window of their own.
+            let mini = Rc::new(MiniMonitor::new(prefs.clone(), histories.clone(), form_gpu.clone(), gpus.clone(), chart_start));
+            let mini_btn = Button::from_icon_name("view-restore-symbolic");
+            mini_btn.set_tooltip_text(Some(&tr!("Mini Monitor")));
+            {
+                let mini = mini.clone();
+                mini_btn.connect_clicked(move |_| mini.present());
+            }
+            header.pack_end(&mini_btn);
+
             stack.set_hexpand(true);
             stack.set_vexpand(true);
 
+            // Settings that something other than this GUI changed, per GPU,
+            // with a description of the change. The banner offers to load
+            // them into the form or to keep the form's values.
+            let external: Rc<RefCell<HashMap<u32, (LiveSettings, String)>>> = Rc::new(RefCell::new(HashMap::new()));
+            // What this GUI last asked each GPU's power limit and offsets to
+            // be, to tell its own changes from outside ones.
+            let requested: Rc<RefCell<HashMap<u32, LiveSettings>>> = Rc::new(RefCell::new(HashMap::new()));
+            // Default power limit of each GPU as last sampled, in mW, for
+            // what resetting it should lead to.
+            let power_defaults: Rc<RefCell<HashMap<u32, u32>>> = Rc::new(RefCell::new(HashMap::new()));
+            let external_label = Label::new(None);
+            external_label.set_wrap(true);
+            external_label.set_xalign(0.0);
+            external_label.set_hexpand(true);
+            let external_bar = gtk4::InfoBar::new();
+            external_bar.set_message_type(MessageType::Warning);
+            external_bar.add_child(&external_label);
+            external_bar.add_button(&tr!("Reload"), gtk4::ResponseType::Accept);
+            external_bar.add_button(&tr!("Keep Mine"), gtk4::ResponseType::Reject);
+            external_bar.set_revealed(false);
+            let refresh_external: Rc<dyn Fn()> = {
+                let external = external.clone();
+                let external_bar = external_bar.clone();
+                let external_label = external_label.clone();
+                let form_gpu = form_gpu.clone();
+                Rc::new(move || match external.borrow().get(&form_gpu.get()) {
+                    Some((_, changes)) => {
+                        external_label.set_text(&tr!("Settings changed outside zelos: {}. Applying would overwrite them.", changes));
+                        external_bar.set_revealed(true);
+                    }
+                    None => external_bar.set_revealed(false),
+                })
+            };
+            {
+                let external = external.clone();
+                let form_gpu = form_gpu.clone();
+                let perf_controls = perf_controls.clone();
+                let mem_clocks = mem_clocks.clone();
+                let refresh_external = refresh_external.clone();
+                external_bar.connect_response(move |_, resp| {
+                    let Some(((power, freq, mem), _)) = external.borrow_mut().remove(&form_gpu.get()) else {
+                        return;
+                    };
+                    // Reload takes the live power limit and offsets and keeps
+                    // the rest of the form; the edit is recorded as usual.
+                    if resp == gtk4::ResponseType::Accept {
+                        let mut sets = perf_controls.read();
+                        sets.power_limit = power.or(sets.power_limit);
+                        sets.freq_offset = freq.or(sets.freq_offset);
+                        sets.mem_offset = mem.or(sets.mem_offset);
+                        perf_controls.load(&sets, &mem_clocks.borrow());
+                    }
+                    refresh_external();
+                });
+            }
+
             let root = GtkBox::new(Orientation::Vertical, 0);
             root.set_hexpand(true);
             root.set_vexpand(true);
             root.append(&header);
+            root.append(&external_bar);
             root.append(&stack);
+            root.append(&activity.pane);
+            {
+                let window = window.clone();
+                let activity = activity.clone();
+                activity.export.clone().connect_clicked(move |_| {
+                    let activity = activity.clone();
+                    save_file_dialog(Some(&window), &tr!("Export Activity Log"), "zelos-activity.log", &[], move |path, _| activity.write_text(path));
+                });
+            }
 
             // Add a small CSS provider to increase progress bar height
             if let Some(display) = gdk::Display::default() {
                 let provider = CssProvider::new();
-                let css = ".metrics-card { padding: 12px; }\n.metrics-card-snug { padding: 8px; }\n.metrics-title { font-weight: 700; }\n.metrics-stat-name { opacity: 0.9; }\n.metrics-stat-value { font-weight: 700; }\n.metrics-row { padding: 6px 10px; }\n.metrics-gauge-big { font-size: 32px; font-weight: 700; }\n.metrics-gauge-mid { font-size: 22px; font-weight: 700; }\n.metrics-gauge-small { opacity: 0.85; }\n.metrics-value { font-weight: 700; }\nlevelbar.nvidia-progress trough { min-height: 28px; border-radius: 8px; }\nlevelbar.nvidia-progress trough block { min-height: 28px; border-radius: 8px; }\nlevelbar.nvidia-progress trough block.filled { background-color: @accent_bg_color; }\nlevelbar.nvidia-progress trough block.empty { background-color: alpha(@window_fg_color, 0.06); }\n\n.perf-section-title { font-weight: 700; font-size: 18px; }\n.perf-card { padding: 14px; }\n.perf-icon-badge { min-width: 36px; min-height: 36px; border-radius: 999px; background-color: transparent; }\n.perf-card-title { font-weight: 700; }\n.perf-card-subtitle { opacity: 0.75; }\n.perf-row { padding: 6px 6px; }\n.perf-row-title { font-weight: 600; }\n.perf-row-value { font-weight: 700; min-width: 56px; }\n/* Make numeric entry fields visually match surrounding cards (no native entry chrome) */\nentry.perf-row-value { background-color: transparent; border: none; padding: 0; }\nentry.perf-row-value:focus { background-color: transparent; box-shadow: none; }\n.perf-stepper { border-radius: 999px; padding: 6px 12px; background-color: alpha(@window_fg_color, 0.06); }\n.perf-disclosure { border-radius: 999px; padding: 4px 10px; background-color: alpha(@window_fg_color, 0.06); }\n.perf-action-primary { border-radius: 999px; padding: 10px 18px; }\n.perf-action-secondary { border-radius: 999px; padding: 10px 18px; }";
+                let css = ".metrics-card { padding: 12px; }\n.metrics-card-snug { padding: 8px; }\n.metrics-title { font-weight: 700; }\n.metrics-stat-name { opacity: 0.9; }\n.metrics-stat-value { font-weight: 700; }\n.metrics-row { padding: 6px 10px; }\n.metrics-gauge-big { font-size: 32px; font-weight: 700; }\n.metrics-gauge-mid { font-size: 22px; font-weight: 700; }\n.metrics-gauge-small { opacity: 0.85; }\n.metrics-value { font-weight: 700; }\n.overview-card { min-width: 220px; }\nlevelbar.nvidia-progress trough { min-height: 28px; border-radius: 8px; }\nlevelbar.nvidia-progress trough block { min-height: 28px; border-radius: 8px; }\nlevelbar.nvidia-progress trough block.filled { background-color: @accent_bg_color; }\nlevelbar.nvidia-progress trough block.empty { background-color: alpha(@window_fg_color, 0.06); }\n\n.perf-section-title { font-weight: 700; font-size: 18px; }\n.perf-card { padding: 14px; }\n.perf-icon-badge { min-width: 36px; min-height: 36px; border-radius: 999px; background-color: transparent; }\n.perf-card-title { font-weight: 700; }\n.perf-card-subtitle { opacity: 0.75; }\n.perf-row { padding: 6px 6px; }\n.perf-row-title { font-weight: 600; }\n.perf-row-value { font-weight: 700; min-width: 56px; }\n/* Make numeric entry fields visually match surrounding cards (no native entry chrome) */\nentry.perf-row-value { background-color: transparent; border: none; padding: 0; }\nentry.perf-row-value:focus { background-color: transparent; box-shadow: none; }\n.perf-stepper { border-radius: 999px; padding: 6px 12px; background-color: alpha(@window_fg_color, 0.06); }\n.perf-disclosure { border-radius: 999px; padding: 4px 10px; background-color: alpha(@window_fg_color, 0.06); }\n.perf-action-primary { border-radius: 999px; padding: 10px 18px; }\n.perf-action-secondary { border-radius: 999px; padding: 10px 18px; }";
                 provider.load_from_data(css);
                 gtk4::style_context_add_provider_for_display(&display, &provider, gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION);
             }
@@ -1298,113 +2304,449 @@ pub mod imp {
             let preview_buffer = preview.buffer();
             let gpu_combo_clone = gpu_combo.clone();
             // Clone adjustments so closures can capture them without moving original values
-            let pa = power_adj.clone();
-            let fa = freq_adj.clone();
-            let ma = mem_adj.clone();
-            let mia = min_adj.clone();
-            let mxa = max_adj.clone();
+            let perf_controls_pv = perf_controls.clone();
             let preview_updater = move || {
-                let active = gpu_combo_clone.active_id();
-                let gpu_id = active.as_deref().unwrap_or("0");
-                let cmd = build_command(gpu_id, (pa.value() * 1000.0).round() as i32, fa.value() as i32, ma.value() as i32, mia.value() as i32, mxa.value() as i32);
-                preview_buffer.set_text(&cmd);
+                let index = gpu_combo_clone.active_id().and_then(|id| id.parse().ok()).unwrap_or(0);
+                let request = ApplyRequest { index, sets: perf_controls_pv.read(), reset_unset: false };
+                preview_buffer.set_text(&request.preview());
             };
 
-            let preview_updater_clone = preview_updater.clone();
-            power_adj.connect_value_changed(move |_| preview_updater_clone());
-            let preview_updater_clone = preview_updater.clone();
-            freq_adj.connect_value_changed(move |_| preview_updater_clone());
-            let preview_updater_clone = preview_updater.clone();
-            mem_adj.connect_value_changed(move |_| preview_updater_clone());
-            let preview_updater_clone = preview_updater.clone();
-            min_adj.connect_value_changed(move |_| preview_updater_clone());
-            let preview_updater_clone = preview_updater.clone();
-            max_adj.connect_value_changed(move |_| preview_updater_clone());
+            perf_controls.connect_changed(preview_updater.clone());
             let preview_updater_clone = preview_updater.clone();
             gpu_combo.connect_changed(move |_| preview_updater_clone());
 
             preview_updater();
 
-            // Disable the service button initially if a service exists and
-            // the current NVML-derived values match the values stored in
-            // the service. Re-enable the button as soon as the user
-            // changes any control.
-            let service_exists = std::path::Path::new(svc_path).exists();
-            let svc_index_clone = svc_index.clone();
-            let svc_power_clone = svc_power;
-            let svc_freq_clone = svc_freq;
-            let svc_mem_clone = svc_mem;
-            let svc_min_clone = svc_min;
-            let svc_max_clone = svc_max;
-            let service_btn_state = service_btn.clone();
-            let combo_state = gpu_combo.clone();
-            let pa_state = power_adj.clone();
-            let fa_state = freq_adj.clone();
-            let ma_state = mem_adj.clone();
-            let mia_state = min_adj.clone();
-            let mxa_state = max_adj.clone();
+            // Per-GPU edits that haven't been saved yet. The GPU the form
+            // currently shows is folded in whenever it is read.
+            let edits: Rc<RefCell<HashMap<u32, Sets>>> = Rc::new(RefCell::new(HashMap::new()));
+            let loading = Rc::new(Cell::new(false));
+
+            // The config as it would be saved right now.
+            let pending_config = {
+                let saved_config = saved_config.clone();
+                let edits = edits.clone();
+                let form_gpu = form_gpu.clone();
+                let perf_controls = perf_controls.clone();
+                Rc::new(move || {
+                    let mut config = saved_config.borrow().clone();
+                    for (index, sets) in edits.borrow().iter() {
+                        config.sets.insert(*index, sets.clone());
+                    }
+                    config.sets.insert(form_gpu.get(), perf_controls.read());
+                    config
+                })
+            };
 
+            // Disable the service button if a boot job exists and the
+            // config it applies already matches the form. Re-enable it as
+            // soon as the user changes any control.
+            let service_btn_state = service_btn.clone();
+            let saved_for_state = saved_config.clone();
+            let pending_for_state = pending_config.clone();
             let check_state = Rc::new(move || {
-                if !service_exists {
-                    service_btn_state.set_sensitive(true);
-                    return;
-                }
-                let active = combo_state.active_id();
-                let gpu_id = active.as_deref().unwrap_or("0");
-                let power_now = (pa_state.value() * 1000.0).round() as i32;
-                let freq_now = fa_state.value() as i32;
-                let mem_now = ma_state.value() as i32;
-                let min_now = mia_state.value() as i32;
-                let max_now = mxa_state.value() as i32;
-
-                let idx_eq = svc_index_clone.as_deref().map(|s| s == gpu_id).unwrap_or(false);
-                let power_eq = svc_power_clone.map(|p| p == power_now).unwrap_or(false);
-                let freq_eq = svc_freq_clone.map(|p| p == freq_now).unwrap_or(false);
-                let mem_eq = svc_mem_clone.map(|p| p == mem_now).unwrap_or(false);
-                let min_eq = svc_min_clone.map(|p| p == min_now).unwrap_or(false);
-                let max_eq = svc_max_clone.map(|p| p == max_now).unwrap_or(false);
-
-                if idx_eq && power_eq && freq_eq && mem_eq && min_eq && max_eq {
-                    service_btn_state.set_sensitive(false);
-                } else {
-                    service_btn_state.set_sensitive(true);
-                }
+                let service_exists = crate::init::Init::detect("/").map(|i| i.is_installed()).unwrap_or(false);
+                let unchanged = pending_for_state().sets == saved_for_state.borrow().sets;
+                service_btn_state.set_sensitive(!(service_exists && unchanged));
             });
 
+            // Remember edits per GPU, and load the selected GPU's values
+            // when the selection changes.
+            {
+                let edits = edits.clone();
+                let form_gpu = form_gpu.clone();
+                let loading = loading.clone();
+                let controls_cl = perf_controls.clone();
+                let record = Rc::new(move || {
+                    if loading.get() {
+                        return;
+                    }
+                    edits.borrow_mut().insert(form_gpu.get(), controls_cl.read());
+                });
+                perf_controls.connect_changed(move || record());
+            }
+            {
+                let edits = edits.clone();
+                let form_gpu = form_gpu.clone();
+                let loading = loading.clone();
+                let controls_cl = perf_controls.clone();
+                let saved_config = saved_config.clone();
+                let mem_clocks = mem_clocks.clone();
+                let power_scale = power_scale.clone();
+                let power_sub = power_sub.clone();
+                gpu_combo.connect_changed(move |combo| {
+                    let Some(index) = combo.active_id().and_then(|id| id.parse::<u32>().ok()) else {
+                        return;
+                    };
+                    form_gpu.set(index);
+                    let edited = edits.borrow().get(&index).cloned();
+                    let sets = edited.unwrap_or_else(|| form_sets(index, saved_config.borrow().sets.get(&index)).0);
+                    *mem_clocks.borrow_mut() = supported_mem_clocks(index);
+                    loading.set(true);
+                    DeviceLimits::query(index, &mem_clocks.borrow()).apply(&controls_cl, &power_scale, &power_sub);
+                    controls_cl.load(&sets, &mem_clocks.borrow());
+                    loading.set(false);
+                });
+            }
+            // After the handler above, so the banner follows the GPU just
+            // selected.
+            {
+                let refresh_external = refresh_external.clone();
+                gpu_combo.connect_changed(move |_| refresh_external());
+            }
+
             // Attach the checker to all controls so any change will re-evaluate
             // the service button state.
             let cs = check_state.clone();
-            power_adj.connect_value_changed(move |_| cs());
-            let cs = check_state.clone();
-            freq_adj.connect_value_changed(move |_| cs());
-            let cs = check_state.clone();
-            mem_adj.connect_value_changed(move |_| cs());
-            let cs = check_state.clone();
-            min_adj.connect_value_changed(move |_| cs());
-            let cs = check_state.clone();
-            max_adj.connect_value_changed(move |_| cs());
+            perf_controls.connect_changed(move || cs());
             let cs = check_state.clone();
             gpu_combo.connect_changed(move |_| cs());
 
             // Run once to set initial state
             (check_state)();
 
-            // NVML handle for periodic metric updates (if available)
-            let nvml_handle = nvml_wrapper::Nvml::init().ok();
+            // Profiles of the selected GPU. Every change is written to the
+            // config file right away.
+            let update_profile_label = {
+                let profile_label = profile_label.clone();
+                let saved_config = saved_config.clone();
+                let form_gpu = form_gpu.clone();
+                let perf_controls = perf_controls.clone();
+                Rc::new(move || {
+                    let config = saved_config.borrow();
+                    let index = form_gpu.get();
+                    let active = config.active_profiles.get(&index).and_then(|name| Some((name, config.profiles.get(&index)?.get(name)?)));
+                    match active {
+                        Some((name, sets)) if *sets == perf_controls.read() => profile_label.set_text(&tr!("Profile: {}", name)),
+                        Some((name, _)) => profile_label.set_text(&tr!("Profile: {} (modified)", name)),
+                        None => profile_label.set_text(""),
+                    }
+                    profile_label.set_visible(active.is_some());
+                })
+            };
+            let refresh_profiles = {
+                let profiles_list = profiles_list.clone();
+                let profiles_title = profiles_title.clone();
+                let profile_apply_btn = profile_apply_btn.clone();
+                let saved_config = saved_config.clone();
+                let form_gpu = form_gpu.clone();
+                let gpu_combo = gpu_combo.clone();
+                let update_profile_label = update_profile_label.clone();
+                Rc::new(move || {
+                    while let Some(row) = profiles_list.row_at_index(0) {
+                        profiles_list.remove(&row);
+                    }
+                    let index = form_gpu.get();
+                    profiles_title.set_text(&tr!("Profiles for {}", gpu_combo.active_text().map(|t| t.to_string()).unwrap_or_else(|| tr!("GPU {}", index))));
+
+                    let config = saved_config.borrow();
+                    let active = config.active_profiles.get(&index);
+                    for name in config.profiles.get(&index).into_iter().flat_map(|p| p.keys()) {
+                        let row_box = GtkBox::new(Orientation::Horizontal, 12);
+                        row_box.set_css_classes(&["perf-row"]);
+                        let name_lbl = Label::new(Some(name));
+                        name_lbl.set_halign(gtk4::Align::Start);
+                        name_lbl.set_hexpand(true);
+                        name_lbl.set_css_classes(&["perf-row-title"]);
+                        row_box.append(&name_lbl);
+                        if active == Some(name) {
+                            let active_lbl = Label::new(Some(&tr!("Active")));
+                            active_lbl.set_css_classes(&["perf-card-subtitle"]);
+                            row_box.append(&active_lbl);
+                        }
+                        let row_apply = Button::with_label(&tr!("Apply"));
+                        row_apply.set_css_classes(&["flat"]);
+                        row_box.append(&row_apply);
+
+                        let row = gtk4::ListBoxRow::new();
+                        row.set_widget_name(name);
+                        row.set_child(Some(&row_box));
+                        profiles_list.append(&row);
+
+                        // One click: select the row and apply it.
+                        let profiles_list = profiles_list.clone();
+                        let profile_apply_btn = profile_apply_btn.clone();
+                        row_apply.connect_clicked(move |_| {
+                            profiles_list.select_row(Some(&row));
+                            profile_apply_btn.emit_clicked();
+                        });
+                    }
+                    drop(config);
+                    update_profile_label();
+                })
+            };
+            refresh_profiles();
+            {
+                let refresh_profiles = refresh_profiles.clone();
+                gpu_combo.connect_changed(move |_| refresh_profiles());
+                let update_profile_label = update_profile_label.clone();
+                perf_controls.connect_changed(move || update_profile_label());
+            }
+            {
+                let buttons = [profile_rename_btn.clone(), profile_duplicate_btn.clone(), profile_delete_btn.clone(), profile_apply_btn.clone()];
+                profiles_list.connect_row_selected(move |_, row| {
+                    for btn in &buttons {
+                        btn.set_sensitive(row.is_some());
+                    }
+                });
+            }
+            let selected_profile = {
+                let profiles_list = profiles_list.clone();
+                Rc::new(move || profiles_list.selected_row().map(|row| row.widget_name().to_string()))
+            };
+            // Saves `config` and, once that worked, makes it the saved config.
+            let commit_profiles = {
+                let window = window.clone();
+                let config_path = config_path.clone();
+                let saved_config = saved_config.clone();
+                let refresh_profiles = refresh_profiles.clone();
+                let activity = activity.clone();
+                Rc::new(move |config: Config| match save_config(&config_path, &config, false, &activity) {
+                    Ok(()) => {
+                        *saved_config.borrow_mut() = config;
+                        refresh_profiles();
+                    }
+                    Err(msg) => show_message(Some(&window), MessageType::Error, ButtonsType::Ok, &tr!("Failed to save profiles: {}", msg)),
+                })
+            };
+
+            {
+                let window = window.clone();
+                let saved_config = saved_config.clone();
+                let form_gpu = form_gpu.clone();
+                let perf_controls = perf_controls.clone();
+                let commit_profiles = commit_profiles.clone();
+                profile_save_btn.connect_clicked(move |_| {
+                    let index = form_gpu.get();
+                    let initial = saved_config.borrow().active_profiles.get(&index).cloned().unwrap_or_default();
+                    let sets = perf_controls.read();
+                    let saved_config = saved_config.clone();
+                    let commit_profiles = commit_profiles.clone();
+                    prompt_name(Some(&window), &tr!("Save the current settings as a profile"), &initial, move |name| {
+                        let mut config = saved_config.borrow().clone();
+                        config.profiles.entry(index).or_default().insert(name, sets.clone());
+                        commit_profiles(config);
+                    });
+                });
+            }
+            {
+                let window = window.clone();
+                let saved_config = saved_config.clone();
+                let form_gpu = form_gpu.clone();
+                let selected_profile = selected_profile.clone();
+                let commit_profiles = commit_profiles.clone();
+                profile_rename_btn.connect_clicked(move |_| {
+                    let Some(old) = selected_profile() else {
+                        return;
+                    };
+                    let index = form_gpu.get();
+                    let window_cl = window.clone();
+                    let saved_config = saved_config.clone();
+                    let commit_profiles = commit_profiles.clone();
+                    let initial = old.clone();
+                    prompt_name(Some(&window), &tr!("Rename profile"), &initial, move |name| {
+                        let mut config = saved_config.borrow().clone();
+                        let profiles = config.profiles.entry(index).or_default();
+                        if name != old && profiles.contains_key(&name) {
+                            show_message(Some(&window_cl), MessageType::Error, ButtonsType::Ok, &tr!("A profile named \"{}\" already exists.", name));
+                            return;
+                        }
+                        let Some(sets) = profiles.remove(&old) else {
+                            return;
+                        };
+                        profiles.insert(name.clone(), sets);
+                        if config.active_profiles.get(&index) == Some(&old) {
+                            config.active_profiles.insert(index, name);
+                        }
+                        commit_profiles(config);
+                    });
+                });
+            }
+            {
+                let saved_config = saved_config.clone();
+                let form_gpu = form_gpu.clone();
+                let selected_profile = selected_profile.clone();
+                let commit_profiles = commit_profiles.clone();
+                profile_duplicate_btn.connect_clicked(move |_| {
+                    let Some(name) = selected_profile() else {
+                        return;
+                    };
+                    let mut config = saved_config.borrow().clone();
+                    let profiles = config.profiles.entry(form_gpu.get()).or_default();
+                    let Some(sets) = profiles.get(&name).cloned() else {
+                        return;
+                    };
+                    let copy = (1..)
+                        .map(|n| if n == 1 { tr!("{} (copy)", name) } else { tr!("{} (copy {})", name, n) })
+                        .find(|copy| !profiles.contains_key(copy))
+                        .unwrap_or_default();
+                    profiles.insert(copy, sets);
+                    commit_profiles(config);
+                });
+            }
+            {
+                let window = window.clone();
+                let saved_config = saved_config.clone();
+                let form_gpu = form_gpu.clone();
+                let selected_profile = selected_profile.clone();
+                let commit_profiles = commit_profiles.clone();
+                profile_delete_btn.connect_clicked(move |_| {
+                    let Some(name) = selected_profile() else {
+                        return;
+                    };
+                    let index = form_gpu.get();
+                    let confirm = MessageDialog::new(Some(&window), gtk4::DialogFlags::MODAL, MessageType::Question, ButtonsType::YesNo, tr!("Delete profile \"{}\"?", name));
+                    let saved_config = saved_config.clone();
+                    let commit_profiles = commit_profiles.clone();
+                    confirm.connect_response(move |dlg, resp| {
+                        dlg.close();
+                        if resp != gtk4::ResponseType::Yes {
+                            return;
+                        }
+                        let mut config = saved_config.borrow().clone();
+                        if let Some(profiles) = config.profiles.get_mut(&index) {
+                            profiles.remove(&name);
+                            if profiles.is_empty() {
+                                config.profiles.remove(&index);
+                            }
+                        }
+                        if config.active_profiles.get(&index) == Some(&name) {
+                            config.active_profiles.remove(&index);
+                        }
+                        commit_profiles(config);
+                    });
+                    confirm.present();
+                });
+            }
+            // Applying goes through `zelos profile apply`, which applies the
+            // profile and makes it the config's settings for the GPU under one
+            // elevation request. The tray menu applies profiles too.
+            let apply_profile: Rc<dyn Fn(u32, String)> = {
+                let window = window.clone();
+                let config_path = config_path.clone();
+                let saved_config = saved_config.clone();
+                let edits = edits.clone();
+                let form_gpu = form_gpu.clone();
+                let loading = loading.clone();
+                let perf_controls = perf_controls.clone();
+                let mem_clocks = mem_clocks.clone();
+                let refresh_profiles = refresh_profiles.clone();
+                let check_state = check_state.clone();
+                let mark_event = mark_event.clone();
+                let notifier = notifier.clone();
+                let activity = activity.clone();
+                let undo = undo.clone();
+                let requested = requested.clone();
+                let power_defaults = power_defaults.clone();
+                Rc::new(move |index, name| {
+                    let Some(sets) = saved_config.borrow().profiles.get(&index).and_then(|p| p.get(&name)).cloned() else {
+                        return;
+                    };
+                    undo.borrow_mut().ensure_baseline(index, || form_sets(index, saved_config.borrow().sets.get(&index)).0);
+                    requested.borrow_mut().insert(index, ApplyRequest { index, sets: sets.clone(), reset_unset: false }.expected(&power_defaults.borrow()));
+                    let result = activity.run(
+                        &tr!("Apply profile \"{}\" to GPU {}", name, index),
+                        std::process::Command::new("pkexec").arg(ApplyRequest::exe()).arg("--file").arg(&config_path).args(["profile", "apply", &name, "--index", &index.to_string(), "--json"]),
+                        ApplyRequest::outcome_lines,
+                    );
+                    match result {
+                        Ok(out) if out.status.success() => {
+                            {
+                                let mut config = saved_config.borrow_mut();
+                                config.sets.insert(index, sets.clone());
+                                config.active_profiles.insert(index, name.clone());
+                            }
+                            mark_event(index, EventKind::Profile, format!("\"{}\"", name));
+                            undo.borrow_mut().record(index, sets.clone(), tr!("Profile \"{}\"", name));
+                            edits.borrow_mut().remove(&index);
+                            if index == form_gpu.get() {
+                                loading.set(true);
+                                perf_controls.load(&sets, &mem_clocks.borrow());
+                                loading.set(false);
+                            }
+                            refresh_profiles();
+                            check_state();
+                        }
+                        Ok(out) => {
+                            let msg = ApplyRequest::failure(&out);
+                            notifier.failure(&tr!("Failed to apply profile \"{}\"", name), &msg);
+                            show_message(Some(&window), MessageType::Error, ButtonsType::Ok, &tr!("Failed to apply profile \"{}\": {}", name, msg));
+                        }
+                        Err(e) => show_message(Some(&window), MessageType::Error, ButtonsType::Ok, &tr!("Failed to run pkexec: {}", e)),
+                    }
+                })
+            };
+            {
+                let apply_profile = apply_profile.clone();
+                let form_gpu = form_gpu.clone();
+                profile_apply_btn.connect_clicked(move |_| {
+                    if let Some(name) = selected_profile() {
+                        apply_profile(form_gpu.get(), name);
+                    }
+                });
+            }
+
+            // Tray indicator, if the desktop has a StatusNotifierItem host.
+            // Closing the window can leave it running.
+            let tray = {
+                let window = window.clone();
+                let app = app.clone();
+                let apply_profile = apply_profile.clone();
+                let prefs = prefs.clone();
+                let mark_event = mark_event.clone();
+                let activity = activity.clone();
+                let requested = requested.clone();
+                let power_defaults = power_defaults.clone();
+                tray::Tray::new(move |action| match action {
+                    TrayAction::Open => window.present(),
+                    TrayAction::ApplyProfile(index, name) => apply_profile(index, name),
+                    TrayAction::Reset(index) => {
+                        requested.borrow_mut().insert(index, ApplyRequest { index, sets: Sets::default(), reset_unset: true }.expected(&power_defaults.borrow()));
+                        let result = activity.run(&tr!("Reset GPU {} to driver defaults", index), std::process::Command::new("pkexec").arg(ApplyRequest::exe()).args(["reset", "--index", &index.to_string(), "--json"]), ApplyRequest::outcome_lines);
+                        match result {
+                            Ok(out) if out.status.success() => mark_event(index, EventKind::Applied, tr!("Reset to driver defaults")),
+                            Ok(out) => {
+                                let msg = ApplyRequest::failure(&out);
+                                show_message(Some(&window), MessageType::Error, ButtonsType::Ok, &tr!("Failed to reset GPU {}: {}", index, msg));
+                            }
+                            Err(e) => show_message(Some(&window), MessageType::Error, ButtonsType::Ok, &tr!("Failed to run pkexec: {}", e)),
+                        }
+                    }
+                    TrayAction::KeepRunning(on) => prefs.borrow_mut().keep_running = on,
+                    TrayAction::Quit => app.quit(),
+                })
+            };
+            if tray.is_some() {
+                let prefs = prefs.clone();
+                window.connect_close_request(move |w| {
+                    if prefs.borrow().keep_running {
+                        w.set_visible(false);
+                        return gtk4::Inhibit(true);
+                    }
+                    gtk4::Inhibit(false)
+                });
+            }
+
+            // Samples arrive from a worker thread, so a slow NVML call never
+            // blocks the UI; the 33 ms redraw timer stays independent of them.
+            let samples = sampler::spawn(gpu_indices.clone(), chart_start, sample_interval.clone());
 
-            // Poll NVML every second to update metrics widgets
             let gauge_state_cl = gauge_state.clone();
             let vram_center_used_cl = vram_center_used.clone();
             let vram_center_total_cl = vram_center_total.clone();
             let vram_gauge_cl = vram_gauge.clone();
 
-            let stat_core_value_cl = stat_core_value.clone();
-            let stat_mem_value_cl = stat_mem_value.clone();
             let stat_temp_value_cl = stat_temp_value.clone();
 
-            let core_clock_history_cl = core_clock_history.clone();
-            let mem_clock_history_cl = mem_clock_history.clone();
-            let chart_start_cl = chart_start;
+            let histories_cl = histories.clone();
+            let charts_cl = charts.clone();
+            let overview_cards_cl = overview_cards.clone();
+            let mark_event_cl = mark_event.clone();
+            // Last seen settings and thermal throttling per GPU, to mark
+            // changes on the charts.
+            let mut last_settings = HashMap::new();
+            let mut last_thermal: HashMap<u32, bool> = HashMap::new();
+            let form_gpu_cl = form_gpu.clone();
 
             let usage_bar_cl = usage_bar.clone();
             let usage_value_cl = usage_value.clone();
@@ -1415,272 +2757,246 @@ pub mod imp {
             let fan_value_cl = fan_center_pct.clone();
             let fan_rpm_value_cl = fan_center_rpm.clone();
             let fan_gauge_cl = fan_gauge.clone();
+            let fan_area_cl = fan_area.clone();
+
+            let saved_config_cl = saved_config.clone();
+            let gpus_cl = gpus.clone();
+            let notifier_cl = notifier.clone();
+            let prefs_cl = prefs.clone();
+            let mini_cl = mini.clone();
+            let external_cl = external.clone();
+            let refresh_external_cl = refresh_external.clone();
+            let requested_cl = requested.clone();
+            let power_defaults_cl = power_defaults.clone();
+
+            samples.attach(None, move |batch: Vec<GpuSample>| {
+                let units = prefs_cl.borrow().units;
+                if let Some(ref tray) = tray {
+                    let line = |s: &GpuSample| {
+                        let temp = s.temp.map(|t| units.temp(t)).unwrap_or_else(|| tr!("N/A"));
+                        let power = s.power.map(|p| units.power(p)).unwrap_or_else(|| tr!("N/A"));
+                        format!("{} · {}", temp, power)
+                    };
+                    let tooltip: Vec<String> = batch.iter().map(|s| tr!("GPU {}: {}", s.index, line(s))).collect();
+                    let selected = batch.iter().find(|s| s.index == form_gpu_cl.get()).or(batch.first());
+                    tray.set_status(&tooltip.join("\n"), &selected.map(line).unwrap_or_default(), selected.and_then(|s| s.temp), units);
+                    tray.set_menu(tray_menu(&saved_config_cl.borrow(), &gpus_cl, prefs_cl.borrow().keep_running));
+                }
 
-            // Move nvml_handle into the timeout closure so it remains alive
-            let nvml_handle = nvml_handle;
-            glib::timeout_add_local(std::time::Duration::from_secs(1), move || {
-                if let Some(ref nvml) = nvml_handle {
-                    if let Ok(dev) = nvml.device_by_index(gpu_index_num) {
-                        // VRAM
-                        if let Ok(mi) = dev.memory_info() {
-                            let used_mib = mi.used / 1024 / 1024;
-                            let total_mib = mi.total / 1024 / 1024;
-                            {
-                                let mut st = gauge_state_cl.borrow_mut();
-                                st.vram_used_mib = used_mib as f64;
-                                st.vram_total_mib = total_mib as f64;
+                for sample in &batch {
+                    notifier_cl.check(sample);
+                    mini_cl.update(sample, units);
+                    let index = &sample.index;
+                    let t = sample.t;
+                    if let Some(history) = histories_cl.borrow_mut().get_mut(index) {
+                        let values = [
+                            (Metric::CoreClock, sample.core_clock.map(|c| c as f64)),
+                            (Metric::MemClock, sample.mem_clock.map(|c| c as f64)),
+                            (Metric::Power, sample.power.map(|p| p as f64 / 1000.0)),
+                            (Metric::Temperature, sample.temp.map(|t| t as f64)),
+                            (Metric::Utilization, sample.utilization.map(|u| u as f64)),
+                            (Metric::FanSpeed, sample.fan.map(|s| s as f64)),
+                        ];
+                        for (metric, value) in values {
+                            if let Some(v) = value {
+                                history.push(metric, t, v);
                             }
-                            vram_center_used_cl.set_text(&format!("{}", used_mib));
-                            vram_center_total_cl.set_text(&format!("/ {} MiB", total_mib));
-                            vram_gauge_cl.queue_draw();
-                        } else {
-                            {
-                                let mut st = gauge_state_cl.borrow_mut();
-                                st.vram_used_mib = 0.0;
-                                st.vram_total_mib = 0.0;
-                            }
-                            vram_center_used_cl.set_text("N/A");
-                            vram_center_total_cl.set_text("");
-                            vram_gauge_cl.queue_draw();
                         }
+                    }
 
-                        // Clocks
-                        match dev.clock_info(nvml_wrapper::enum_wrappers::device::Clock::Graphics) {
-                            Ok(clk) => {
-                                stat_core_value_cl.set_text(&format!("{} MHz", clk));
-
-                                // Push into history (time-based)
-                                {
-                                    let mut st = core_clock_history_cl.borrow_mut();
-                                    let t = chart_start_cl.elapsed().as_secs_f64();
-                                    st.points.push_back((t, clk as f64));
-                                    while st.points.len() > 300 {
-                                        st.points.pop_front();
-                                    }
-                                }
-                            }
-                            Err(_) => {
-                                stat_core_value_cl.set_text("N/A");
-                            }
-                        }
+                    if let Some(default) = sample.power_default {
+                        power_defaults_cl.borrow_mut().insert(*index, default);
+                    }
 
-                        match dev.clock_info(nvml_wrapper::enum_wrappers::device::Clock::Memory) {
-                            Ok(clk) => {
-                                stat_mem_value_cl.set_text(&format!("{} MHz", clk));
-                                {
-                                    let mut st = mem_clock_history_cl.borrow_mut();
-                                    let t = chart_start_cl.elapsed().as_secs_f64();
-                                    st.points.push_back((t, clk as f64));
-                                    while st.points.len() > 300 {
-                                        st.points.pop_front();
-                                    }
-                                }
+                    // Settings changed by anything but this GUI
+                    let settings = sample.settings;
+                    if let Some(prev) = last_settings.insert(*index, settings).filter(|&prev| prev != settings) {
+                        let ours = changed_by_us(prev, settings, requested_cl.borrow_mut().entry(*index).or_default());
+                        if ours {
+                            external_cl.borrow_mut().remove(index);
+                        } else {
+                            let mut changes = Vec::new();
+                            if prev.0 != settings.0 {
+                                let w = |v: Option<u32>| v.map(|v| units.power(v)).unwrap_or_else(|| tr!("N/A"));
+                                changes.push(tr!("power limit {} → {}", w(prev.0), w(settings.0)));
                             }
-                            Err(_) => {
-                                stat_mem_value_cl.set_text("N/A");
+                            let mhz = |v: Option<i32>| v.map(|v| format!("{} MHz", number(v as f64, 0))).unwrap_or_else(|| tr!("N/A"));
+                            if prev.1 != settings.1 {
+                                changes.push(tr!("core offset {} → {}", mhz(prev.1), mhz(settings.1)));
                             }
-                        }
-
-                        // Temperature (GPU)
-                        let temp_text = match dev.temperature(nvml_wrapper::enum_wrappers::device::TemperatureSensor::Gpu) {
-                            Ok(g) => format!("{} °C", g),
-                            Err(_) => "N/A".to_string(),
-                        };
-                        stat_temp_value_cl.set_text(&temp_text);
-
-                        // Fan Speed (try multiple fan indices; use first successful reading)
-                        let mut fans_text = "N/A".to_string();
-                        let mut fan_rpm_text = "N/A".to_string();
-                        let mut fan_pct_opt: Option<f64> = None;
-                        for i in 0u32..4u32 {
-                            if let Ok(s) = dev.fan_speed(i) {
-                                fan_pct_opt = Some(s as f64);
-                                fans_text = format!("{}%", s);
-                                break;
+                            if prev.2 != settings.2 {
+                                changes.push(tr!("memory offset {} → {}", mhz(prev.2), mhz(settings.2)));
                             }
+                            mark_event_cl(*index, EventKind::External, changes.join("; "));
+                            external_cl.borrow_mut().insert(*index, (settings, changes.join("; ")));
                         }
+                        refresh_external_cl();
+                    }
 
-                        // Fan RPM (may be unsupported on some devices/drivers)
-                        for i in 0u32..4u32 {
-                            if let Ok(rpm) = dev.fan_speed_rpm(i) {
-                                fan_rpm_text = format!("{} RPM", rpm);
-                                break;
-                            }
+                    // Thermal throttling onset
+                    if let Some(reasons) = sample.throttle {
+                        use nvml_wrapper::bitmasks::device::ThrottleReasons;
+                        let thermal = reasons.intersects(ThrottleReasons::SW_THERMAL_SLOWDOWN | ThrottleReasons::HW_THERMAL_SLOWDOWN);
+                        if thermal && !last_thermal.insert(*index, thermal).unwrap_or(false) {
+                            let kind = if reasons.contains(ThrottleReasons::HW_THERMAL_SLOWDOWN) { tr!("Hardware") } else { tr!("Software") };
+                            let temp = sample.temp.map(|t| tr!(" at {}", units.temp(t))).unwrap_or_default();
+                            mark_event_cl(*index, EventKind::Throttle, tr!("{} thermal slowdown{}", kind, temp));
+                        } else if !thermal {
+                            last_thermal.insert(*index, false);
                         }
-                        fan_value_cl.set_text(&fans_text);
-                        fan_rpm_value_cl.set_text(&fan_rpm_text);
+                    }
+
+                    // Overview card readouts
+                    if let Some((_, card)) = overview_cards_cl.iter().find(|(i, _)| i == index) {
+                        card.vram.set_text(&sample.memory.map(|(used, total)| tr!("{} / {} MiB", number(used as f64, 0), number(total as f64, 0))).unwrap_or_else(|| tr!("N/A")));
+                        card.core.set_text(&sample.core_clock.map(|c| format!("{} MHz", number(c as f64, 0))).unwrap_or_else(|| tr!("N/A")));
+                        card.temp.set_text(&sample.temp.map(|g| units.temp(g)).unwrap_or_else(|| tr!("N/A")));
+                        card.power.set_text(&match (sample.power, sample.power_limit) {
+                            (Some(cur), Some(limit)) => format!("{} / {}", number(units.power_value(cur as f64), 0), units.power(limit)),
+                            (Some(cur), None) => units.power(cur),
+                            _ => tr!("N/A"),
+                        });
+                    }
+
+                    // The detail view only shows the selected GPU.
+                    if *index != form_gpu_cl.get() {
+                        continue;
+                    }
+                    // VRAM
+                    if let Some((used_mib, total_mib)) = sample.memory {
                         {
                             let mut st = gauge_state_cl.borrow_mut();
-                            st.fan_pct = fan_pct_opt.unwrap_or(0.0);
+                            st.vram_used_mib = used_mib as f64;
+                            st.vram_total_mib = total_mib as f64;
                         }
-                        fan_gauge_cl.queue_draw();
-
-                        // Utilization -> usage progress bar
-                        if let Ok(u) = dev.utilization_rates() {
-                            usage_bar_cl.set_min_value(0.0);
-                            usage_bar_cl.set_max_value(100.0);
-                            usage_bar_cl.set_value(u.gpu as f64);
-                            usage_value_cl.set_text(&format!("{}%", u.gpu));
-                        } else {
-                            usage_bar_cl.set_value(0.0);
-                            usage_value_cl.set_text("N/A");
+                        vram_center_used_cl.set_text(&number(used_mib as f64, 0));
+                        vram_center_total_cl.set_text(&tr!("/ {} MiB", number(total_mib as f64, 0)));
+                        vram_gauge_cl.queue_draw();
+                    } else {
+                        {
+                            let mut st = gauge_state_cl.borrow_mut();
+                            st.vram_used_mib = 0.0;
+                            st.vram_total_mib = 0.0;
                         }
+                        vram_center_used_cl.set_text(&tr!("N/A"));
+                        vram_center_total_cl.set_text("");
+                        vram_gauge_cl.queue_draw();
+                    }
 
-                        // Power usage -> power progress bar
-                        match (dev.power_usage(), dev.enforced_power_limit()) {
-                            (Ok(cur), Ok(limit)) if limit > 0 => {
-                                let cur_w = (cur as f64) / 1000.0;
-                                let lim_w = (limit as f64) / 1000.0;
-                                power_bar_cl.set_min_value(0.0);
-                                power_bar_cl.set_max_value(lim_w);
-                                power_bar_cl.set_value(cur_w);
-                                power_value_cl.set_text(&format!("{:.2} / {:.2} W", cur_w, lim_w));
-                            }
-                            (Ok(cur), Ok(limit)) => {
-                                let cur_w = (cur as f64) / 1000.0;
-                                let lim_w = (limit as f64) / 1000.0;
-                                power_bar_cl.set_min_value(0.0);
-                                power_bar_cl.set_max_value(lim_w);
-                                power_bar_cl.set_value(0.0);
-                                power_value_cl.set_text(&format!("{:.2} / {:.2} W", cur_w, lim_w));
-                            }
-                            (Ok(cur), Err(_)) => {
-                                let cur_w = (cur as f64) / 1000.0;
-                                power_bar_cl.set_min_value(0.0);
-                                power_bar_cl.set_max_value(cur_w.max(1.0));
-                                power_bar_cl.set_value(cur_w);
-                                power_value_cl.set_text(&format!("{:.2} W", cur_w));
-                            }
-                            _ => {
-                                power_bar_cl.set_min_value(0.0);
-                                power_bar_cl.set_max_value(1.0);
-                                power_bar_cl.set_value(0.0);
-                                power_value_cl.set_text("N/A");
-                            }
+                    // Latest chart values
+                    for chart in charts_cl.iter() {
+                        chart.refresh();
+                    }
+
+                    // Temperature (GPU)
+                    stat_temp_value_cl.set_text(&sample.temp.map(|g| units.temp(g)).unwrap_or_else(|| tr!("N/A")));
+                    gauge_state_cl.borrow_mut().gpu_temp = sample.temp.map(|t| t as f64);
+
+                    // Fan speed and RPM (RPM may be unsupported on some devices/drivers)
+                    fan_value_cl.set_text(&sample.fan.map(|s| format!("{}%", s)).unwrap_or_else(|| tr!("N/A")));
+                    fan_rpm_value_cl.set_text(&sample.fan_rpm.map(|rpm| tr!("{} RPM", number(rpm as f64, 0))).unwrap_or_else(|| tr!("N/A")));
+                    gauge_state_cl.borrow_mut().fan_pct = sample.fan.map(|s| s as f64).unwrap_or(0.0);
+                    fan_gauge_cl.queue_draw();
+                    fan_area_cl.queue_draw();
+
+                    // Utilization -> usage progress bar
+                    if let Some(u) = sample.utilization {
+                        usage_bar_cl.set_min_value(0.0);
+                        usage_bar_cl.set_max_value(100.0);
+                        usage_bar_cl.set_value(u as f64);
+                        usage_value_cl.set_text(&format!("{}%", u));
+                    } else {
+                        usage_bar_cl.set_value(0.0);
+                        usage_value_cl.set_text(&tr!("N/A"));
+                    }
+
+                    // Power usage -> power progress bar
+                    match (sample.power, sample.power_limit) {
+                        (Some(cur), Some(limit)) if limit > 0 => {
+                            let cur_w = (cur as f64) / 1000.0;
+                            let lim_w = (limit as f64) / 1000.0;
+                            power_bar_cl.set_min_value(0.0);
+                            power_bar_cl.set_max_value(lim_w);
+                            power_bar_cl.set_value(cur_w);
+                            power_value_cl.set_text(&format!("{} / {}", units.power_precise(cur), units.power_precise(limit)));
+                        }
+                        (Some(cur), Some(limit)) => {
+                            let lim_w = (limit as f64) / 1000.0;
+                            power_bar_cl.set_min_value(0.0);
+                            power_bar_cl.set_max_value(lim_w);
+                            power_bar_cl.set_value(0.0);
+                            power_value_cl.set_text(&format!("{} / {}", units.power_precise(cur), units.power_precise(limit)));
+                        }
+                        (Some(cur), None) => {
+                            let cur_w = (cur as f64) / 1000.0;
+                            power_bar_cl.set_min_value(0.0);
+                            power_bar_cl.set_max_value(cur_w.max(1.0));
+                            power_bar_cl.set_value(cur_w);
+                            power_value_cl.set_text(&units.power_precise(cur));
+                        }
+                        _ => {
+                            power_bar_cl.set_min_value(0.0);
+                            power_bar_cl.set_max_value(1.0);
+                            power_bar_cl.set_value(0.0);
+                            power_value_cl.set_text(&tr!("N/A"));
                         }
                     }
                 }
                 glib::Continue(true)
             });
 
-            // Service button handler
+            // Service button handler: save the config, then install a boot
+            // job that applies it, under a single elevation request.
             let service_btn_clone = service_btn.clone();
-            let gpu_for_service = gpu_combo.clone();
             let window_for_service = window.clone();
-            // clones for service handler
-            let pa2 = power_adj.clone();
-            let fa2 = freq_adj.clone();
-            let ma2 = mem_adj.clone();
-            let mia2 = min_adj.clone();
-            let mxa2 = max_adj.clone();
+            let config_path_for_service = config_path.clone();
+            let saved_for_service = saved_config.clone();
+            let edits_for_service = edits.clone();
+            let pending_for_service = pending_config.clone();
+            let check_for_service = check_state.clone();
+            let activity_for_service = activity.clone();
             service_btn.connect_clicked(move |_| {
-                let active = gpu_for_service.active_id();
-                let gpu_id = active.as_deref().unwrap_or("0");
-                let cmd = build_command(gpu_id, (pa2.value() * 1000.0).round() as i32, fa2.value() as i32, ma2.value() as i32, mia2.value() as i32, mxa2.value() as i32);
-
-                let service_path = "/etc/systemd/system/zelos.service";
-                let exists = std::path::Path::new(service_path).exists();
-
-                let content = format!("[Unit]\nDescription=NVIDIA Overclocking Service\nAfter=network.target\n\n[Service]\nExecStart={}\nUser=root\nRestart=on-failure\n\n[Install]\nWantedBy=multi-user.target\n", cmd);
-
-                let tmp = std::env::temp_dir().join("zelos.service.tmp");
-                if let Err(e) = std::fs::write(&tmp, content) {
-                    show_message(Some(&window_for_service), MessageType::Error, ButtonsType::Ok, &format!("Failed to write temp service file: {}", e));
-                    return;
-                }
-
-                // Create a small helper script that performs the install + systemctl steps.
-                // This script will be executed once via `pkexec`, so the user is prompted for
-                // elevation only a single time. If elevation is denied, nothing is performed.
-                let script_path = std::env::temp_dir().join("zelos_install.sh");
-                let action = if !exists { "create" } else { "update" };
-                                let script = r#"#!/bin/sh
-                set -e
-                SERVICE_PATH=/etc/systemd/system/zelos.service
-                TMP_SERVICE="$1"
-                ACTION="$2"
-                if ! install -m 644 "$TMP_SERVICE" "$SERVICE_PATH"; then
-                    echo "failed to install service" >&2
-                    exit 2
-                fi
-                if ! systemctl daemon-reload; then
-                    echo "failed to daemon-reload" >&2
-                    exit 3
-                fi
-                if [ "$ACTION" = "create" ]; then
-                    if ! systemctl enable --now zelos; then
-                        echo "failed to enable/start service" >&2
-                        exit 4
-                    fi
-                else
-                    if ! systemctl restart zelos; then
-                        echo "failed to restart service" >&2
-                        exit 5
-                    fi
-                fi
-                exit 0
-                "#.to_string();
-
-                if let Err(e) = std::fs::write(&script_path, script) {
-                    show_message(Some(&window_for_service), MessageType::Error, ButtonsType::Ok, &format!("Failed to write helper script: {}", e));
-                    return;
-                }
-                if let Err(e) = std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o755)) {
-                    show_message(Some(&window_for_service), MessageType::Error, ButtonsType::Ok, &format!("Failed to set exec perms on helper script: {}", e));
+                let Some(init) = crate::init::Init::detect("/") else {
+                    show_message(Some(&window_for_service), MessageType::Error, ButtonsType::Ok, &tr!("No supported init system detected. Zelos can install a boot job for systemd, OpenRC and runit."));
                     return;
-                }
-
-                let result = std::process::Command::new("pkexec").arg(script_path.to_str().unwrap()).arg(tmp.to_str().unwrap()).arg(action).output();
-                match result {
-                    Ok(out) => {
-                            if out.status.success() {
-                            show_message(Some(&window_for_service), MessageType::Info, ButtonsType::Ok, if !exists { "Service created, enabled and started." } else { "Service updated and restarted." });
-                            // Update the service button text. Calling `set_label`
-                            // is a reliable way to change the visible label even
-                            // when the button contains a custom child on many
-                            // themes.
-                            service_btn_clone.set_label("Update Service");
-                        } else {
-                            let mut msg = String::new();
-                            if !out.stdout.is_empty() {
-                                msg.push_str(&String::from_utf8_lossy(&out.stdout));
-                            }
-                            if !out.stderr.is_empty() {
-                                if !msg.is_empty() { msg.push('\n'); }
-                                msg.push_str(&String::from_utf8_lossy(&out.stderr));
-                            }
-                            if msg.is_empty() {
-                                msg = format!("Process exited with status: {}", out.status);
-                            }
-                            show_message(Some(&window_for_service), MessageType::Error, ButtonsType::Ok, &format!("Failed to install/update service: {}", msg));
-                        }
-                    }
-                    Err(e) => {
-                        show_message(Some(&window_for_service), MessageType::Error, ButtonsType::Ok, &format!("Failed to run pkexec: {}", e));
+                };
+                let exists = init.is_installed();
+
+                let config = pending_for_service();
+                match save_config(&config_path_for_service, &config, true, &activity_for_service) {
+                    Ok(()) => {
+                        *saved_for_service.borrow_mut() = config;
+                        edits_for_service.borrow_mut().clear();
+                        show_message(Some(&window_for_service), MessageType::Info, ButtonsType::Ok, &if !exists { tr!("Config saved; {} job created, enabled and started.", init.system) } else { tr!("Config saved; {} job updated and restarted.", init.system) });
+                        // Update the service button text. Calling `set_label`
+                        // is a reliable way to change the visible label even
+                        // when the button contains a custom child on many
+                        // themes.
+                        service_btn_clone.set_label(&tr!("Update Service"));
+                        check_for_service();
                     }
+                    Err(msg) => show_message(Some(&window_for_service), MessageType::Error, ButtonsType::Ok, &tr!("Failed to save config/install service: {}", msg)),
                 }
-
-                // Cleanup temporary files regardless of success or failure. Ignore errors.
-                let _ = std::fs::remove_file(&tmp);
-                let _ = std::fs::remove_file(&script_path);
             });
 
             // Apply handler
             let window_clone = window.clone();
-            let preview_clone = preview.clone();
+            let form_gpu_apply = form_gpu.clone();
+            let perf_controls_apply = perf_controls.clone();
+            let mark_event_apply = mark_event.clone();
+            let notifier_apply = notifier.clone();
+            let external_apply = external.clone();
+            let activity_apply = activity.clone();
+            let undo_apply = undo.clone();
+            let saved_config_apply = saved_config.clone();
+            let refresh_external_apply = refresh_external.clone();
+            let requested_apply = requested.clone();
+            let power_defaults_apply = power_defaults.clone();
             apply.connect_clicked(move |_| {
-                let buffer = preview_clone.buffer();
-                let start = buffer.start_iter();
-                let end = buffer.end_iter();
-                let text = buffer.text(&start, &end, true).to_string();
+                let request = ApplyRequest { index: form_gpu_apply.get(), sets: perf_controls_apply.read(), reset_unset: false };
 
-                if text.trim().is_empty() {
-                    show_message(Some(&window_clone), MessageType::Warning, ButtonsType::Ok, "Command is empty");
-                    return;
-                }
-
-                let confirm = MessageDialog::new(Some(&window_clone), gtk4::DialogFlags::MODAL, MessageType::Question, ButtonsType::YesNo, "Are you sure?");
-                confirm.set_secondary_text(Some("This will apply any changes you've made to your GPU"));
+                let confirm = MessageDialog::new(Some(&window_clone), gtk4::DialogFlags::MODAL, MessageType::Question, ButtonsType::YesNo, tr!("Are you sure?"));
+                confirm.set_secondary_text(Some(&tr!("This will apply any changes you've made to your GPU")));
                 // center any label children inside the dialog's content area
                 let area = confirm.content_area();
                 // remove any auto-created children so we can insert our own centered labels
@@ -1698,13 +3014,13 @@ pub mod imp {
 
                 // Headline label
                 let title_label = Label::new(None);
-                title_label.set_markup("<b>Are you sure?</b>");
+                title_label.set_markup(&format!("<b>{}</b>", glib::markup_escape_text(&tr!("Are you sure?"))));
                 title_label.set_halign(gtk4::Align::Center);
                 title_label.set_valign(gtk4::Align::Center);
                 title_label.set_widget_name("confirm-title");
                 title_label.set_margin_bottom(6);
                 // Context label
-                let context_label = Label::new(Some("This will apply any changes you've made to your GPU"));
+                let context_label = Label::new(Some(&tr!("This will apply any changes you've made to your GPU")));
                 context_label.set_halign(gtk4::Align::Center);
                 context_label.set_valign(gtk4::Align::Center);
                 context_label.set_widget_name("confirm-context");
@@ -1715,36 +3031,40 @@ pub mod imp {
                 confirm.set_default_size(520, 140);
 
                 let win_resp = window_clone.clone();
-                let preview_resp = preview_clone.clone();
+                let mark_event = mark_event_apply.clone();
+                let notifier = notifier_apply.clone();
+                let external = external_apply.clone();
+                let refresh_external = refresh_external_apply.clone();
+                let activity = activity_apply.clone();
+                let undo = undo_apply.clone();
+                let saved_config = saved_config_apply.clone();
+                let requested = requested_apply.clone();
+                let power_defaults = power_defaults_apply.clone();
                 confirm.connect_response(move |dlg, resp| {
                     dlg.close();
                     if resp == gtk4::ResponseType::Yes {
-                        let buffer = preview_resp.buffer();
-                        let start = buffer.start_iter();
-                        let end = buffer.end_iter();
-                        let text = buffer.text(&start, &end, true).to_string();
-                        let mut parts: Vec<&str> = text.split_whitespace().collect();
-                        if parts.is_empty() { return; }
-                        let program = parts.remove(0);
-                        let args: Vec<String> = parts.iter().map(|s| s.to_string()).collect();
-                        let result = std::process::Command::new("pkexec").arg(program).args(&args).output();
-
-                        match result {
-                            Ok(out) => {
-                                let mut shown = String::new();
-                                if !out.stdout.is_empty() {
-                                    shown.push_str("STDOUT:\n");
-                                    shown.push_str(&String::from_utf8_lossy(&out.stdout));
+                        let index = request.index;
+                        undo.borrow_mut().ensure_baseline(index, || form_sets(index, saved_config.borrow().sets.get(&index)).0);
+                        requested.borrow_mut().insert(index, request.expected(&power_defaults.borrow()));
+                        match request.run(&activity) {
+                            Ok(outcomes) => {
+                                let applied: Vec<String> = outcomes.iter().filter(|o| o.error.is_none()).map(|o| o.to_string()).collect();
+                                if !applied.is_empty() {
+                                    mark_event(request.index, EventKind::Applied, applied.join("; "));
+                                    undo.borrow_mut().record(request.index, request.sets.clone(), tr!("Applied"));
+                                    // The form's values won over the outside change.
+                                    external.borrow_mut().remove(&request.index);
+                                    refresh_external();
                                 }
-                                if !out.stderr.is_empty() {
-                                    shown.push_str("\nSTDERR:\n");
-                                    shown.push_str(&String::from_utf8_lossy(&out.stderr));
+                                let rejected: Vec<String> = outcomes.iter().filter(|o| o.error.is_some()).map(|o| o.to_string()).collect();
+                                if !rejected.is_empty() {
+                                    notifier.failure(&tr!("GPU {} rejected some settings", request.index), &rejected.join("\n"));
                                 }
-                                if shown.is_empty() { shown = format!("Process exited with status: {}", out.status); }
-                                show_message(Some(&win_resp), MessageType::Info, ButtonsType::Ok, &shown);
+                                show_outcomes(Some(&win_resp), &outcomes);
                             }
                             Err(e) => {
-                                show_message(Some(&win_resp), MessageType::Error, ButtonsType::Ok, &format!("Failed to execute: {}", e));
+                                notifier.failure(&tr!("Failed to apply settings to GPU {}", request.index), &e);
+                                show_message(Some(&win_resp), MessageType::Error, ButtonsType::Ok, &tr!("Failed to apply settings: {}", e));
                             }
                         }
                     }
@@ -1752,6 +3072,133 @@ pub mod imp {
                 confirm.present();
             });
 
+            // Undo history of the selected GPU: newest first, click a state
+            // to apply it again.
+            let refresh_history: Rc<dyn Fn()> = {
+                let undo = undo.clone();
+                let form_gpu = form_gpu.clone();
+                let history_list = history_list.clone();
+                let undo_btn = undo_btn.clone();
+                let redo_btn = redo_btn.clone();
+                Rc::new(move || {
+                    while let Some(row) = history_list.row_at_index(0) {
+                        history_list.remove(&row);
+                    }
+                    let index = form_gpu.get();
+                    let undo = undo.borrow();
+                    let (states, current) = undo.states(index);
+                    for (position, state) in states.iter().enumerate().rev() {
+                        let row_box = GtkBox::new(Orientation::Horizontal, 12);
+                        row_box.set_css_classes(&["perf-row"]);
+                        let text = GtkBox::new(Orientation::Vertical, 2);
+                        text.set_hexpand(true);
+                        let title = Label::new(Some(&format!("{}  {}", state.time(), state.label)));
+                        title.set_halign(gtk4::Align::Start);
+                        title.set_css_classes(&["perf-row-title"]);
+                        let summary = Label::new(Some(&state.summary()));
+                        summary.set_halign(gtk4::Align::Start);
+                        summary.set_wrap(true);
+                        summary.set_css_classes(&["perf-card-subtitle"]);
+                        text.append(&title);
+                        text.append(&summary);
+                        row_box.append(&text);
+                        if position == current {
+                            let current_lbl = Label::new(Some(&tr!("Current")));
+                            current_lbl.set_css_classes(&["perf-card-subtitle"]);
+                            row_box.append(&current_lbl);
+                        }
+                        let row = gtk4::ListBoxRow::new();
+                        row.set_widget_name(&position.to_string());
+                        row.set_activatable(position != current);
+                        row.set_child(Some(&row_box));
+                        history_list.append(&row);
+                    }
+                    undo_btn.set_sensitive(undo.previous(index).is_some());
+                    redo_btn.set_sensitive(undo.next(index).is_some());
+                })
+            };
+            refresh_history();
+            {
+                let refresh_history = refresh_history.clone();
+                gpu_combo.connect_changed(move |_| refresh_history());
+            }
+            {
+                let refresh_history = refresh_history.clone();
+                history_popover.connect_show(move |_| refresh_history());
+            }
+            // Applies the state at `position` of the selected GPU's history
+            // directly, since picking it is the confirmation.
+            let restore: Rc<dyn Fn(usize)> = {
+                let window = window.clone();
+                let undo = undo.clone();
+                let form_gpu = form_gpu.clone();
+                let edits = edits.clone();
+                let loading = loading.clone();
+                let perf_controls = perf_controls.clone();
+                let mem_clocks = mem_clocks.clone();
+                let check_state = check_state.clone();
+                let mark_event = mark_event.clone();
+                let activity = activity.clone();
+                let history_popover = history_popover.clone();
+                let refresh_history = refresh_history.clone();
+                let requested = requested.clone();
+                let power_defaults = power_defaults.clone();
+                Rc::new(move |position| {
+                    let index = form_gpu.get();
+                    let Some(state) = undo.borrow().get(index, position).cloned() else {
+                        return;
+                    };
+                    history_popover.popdown();
+                    let request = ApplyRequest { index, sets: state.sets.clone(), reset_unset: true };
+                    requested.borrow_mut().insert(index, request.expected(&power_defaults.borrow()));
+                    match request.run(&activity) {
+                        Ok(outcomes) => {
+                            if outcomes.iter().any(|o| o.error.is_none()) {
+                                undo.borrow_mut().set_current(index, position);
+                                mark_event(index, EventKind::Applied, tr!("Back to {} from {}", state.label, state.time()));
+                                edits.borrow_mut().remove(&index);
+                                loading.set(true);
+                                perf_controls.load(&state.sets, &mem_clocks.borrow());
+                                loading.set(false);
+                                check_state();
+                            }
+                            show_outcomes(Some(&window), &outcomes);
+                        }
+                        Err(e) => show_message(Some(&window), MessageType::Error, ButtonsType::Ok, &tr!("Failed to apply settings: {}", e)),
+                    }
+                    refresh_history();
+                })
+            };
+            {
+                let restore = restore.clone();
+                history_list.connect_row_activated(move |_, row| {
+                    if let Ok(position) = row.widget_name().parse() {
+                        restore(position);
+                    }
+                });
+            }
+            {
+                let undo = undo.clone();
+                let form_gpu = form_gpu.clone();
+                let restore = restore.clone();
+                undo_btn.connect_clicked(move |_| {
+                    let previous = undo.borrow().previous(form_gpu.get());
+                    if let Some(position) = previous {
+                        restore(position);
+                    }
+                });
+            }
+            {
+                let undo = undo.clone();
+                let form_gpu = form_gpu.clone();
+                redo_btn.connect_clicked(move |_| {
+                    let next = undo.borrow().next(form_gpu.get());
+                    if let Some(position) = next {
+                        restore(position);
+                    }
+                });
+            }
+
             window.set_content(Some(&root));
 
             // Size the window based on the Metrics tab's natural size, then
@@ -1772,12 +3219,45 @@ pub mod imp {
                 // Prevent dynamic content from increasing the width.
                 window.set_size_request(target_w, -1);
             }
-            if let Some(prev) = prev_page {
-                stack.set_visible_child_name(&prev);
+            if let Some((w, h)) = prefs.borrow().window_size {
+                window.set_default_size(w.max(target_w), h);
+            }
+            // Reopen on the tab that was visible last time, if it still exists.
+            let saved_tab = prefs.borrow().tab.clone().filter(|tab| stack.child_by_name(tab).is_some());
+            if let Some(page) = saved_tab.or(prev_page) {
+                stack.set_visible_child_name(&page);
             } else {
                 stack.set_visible_child_name("performance");
             }
+
+            // Remember the session on the way out. GTK keeps the default size
+            // in step with the window, so it's still valid once it's gone.
+            {
+                let window = window.clone();
+                let stack = stack.clone();
+                let form_gpu = form_gpu.clone();
+                let chart_view = chart_view.clone();
+                let sample_interval = sample_interval.clone();
+                let prefs = prefs.clone();
+                app.connect_shutdown(move |_| {
+                    let mut prefs = prefs.borrow_mut();
+                    let (w, h) = window.default_size();
+                    if w > 0 && h > 0 {
+                        prefs.window_size = Some((w, h));
+                    }
+                    prefs.last_gpu = Some(form_gpu.get());
+                    prefs.tab = stack.visible_child_name().map(|s| s.to_string());
+                    prefs.chart_window_secs = chart_view.window_secs();
+                    prefs.sample_interval_ms = sample_interval.load(Ordering::Relaxed);
+                    if let Err(e) = prefs.save() {
+                        eprintln!("Failed to save preferences: {}", e);
+                    }
+                });
+            }
             window.show();
+            if prefs.borrow().mini_monitor.open {
+                mini.present();
+            }
         });
 
         app.run();
@@ -0,0 +1,240 @@
+// History of the privileged actions the GUI ran, kept across sessions
+
+use adw::prelude::*;
+use gtk4::{Box as GtkBox, Button, Expander, Label, ListBox, Orientation, ScrolledWindow};
+use libadwaita as adw;
+use serde::{Deserialize, Serialize};
+
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::i18n::tr;
+
+/// Entries older than the newest this many are dropped.
+const CAPACITY: usize = 500;
+
+/// What came of asking pkexec for elevation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Elevation {
+    Granted,
+    /// The authentication dialog was dismissed
+    Dismissed,
+    /// Not authorized, or authentication failed
+    Denied,
+    /// pkexec couldn't be started
+    Failed,
+}
+
+impl Elevation {
+    fn of(output: &std::io::Result<Output>) -> Self {
+        match output.as_ref().map(|out| out.status.code()) {
+            Err(_) => Elevation::Failed,
+            Ok(Some(126)) => Elevation::Dismissed,
+            Ok(Some(127)) => Elevation::Denied,
+            Ok(_) => Elevation::Granted,
+        }
+    }
+
+    fn title(self) -> String {
+        match self {
+            Elevation::Granted => tr!("elevation granted"),
+            Elevation::Dismissed => tr!("elevation dismissed"),
+            Elevation::Denied => tr!("elevation denied"),
+            Elevation::Failed => tr!("pkexec failed to start"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub action: String,
+    /// The command line that was run
+    pub request: String,
+    pub elevation: Elevation,
+    pub succeeded: bool,
+    /// Result of each setting, where the command reports them
+    #[serde(default)]
+    pub outcomes: Vec<String>,
+    #[serde(default)]
+    pub stderr: String,
+}
+
+impl Entry {
+    fn time(&self) -> String {
+        humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(self.timestamp)).to_string()
+    }
+
+    fn status(&self) -> String {
+        tr!("{}, {}", if self.succeeded { tr!("succeeded") } else { tr!("failed") }, self.elevation.title())
+    }
+
+    fn write_text(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "{}  {} ({})", self.time(), self.action, self.status())?;
+        writeln!(out, "  $ {}", self.request)?;
+        for outcome in &self.outcomes {
+            writeln!(out, "  {}", outcome)?;
+        }
+        for line in self.stderr.lines() {
+            writeln!(out, "  stderr: {}", line)?;
+        }
+        Ok(())
+    }
+
+    fn row(&self) -> adw::ExpanderRow {
+        let row = adw::ExpanderRow::new();
+        // Row titles are markup.
+        let title = format!("{}  {}", self.time().replace('T', " ").trim_end_matches('Z'), self.action);
+        row.set_title(&gtk4::glib::markup_escape_text(&title));
+        row.set_subtitle(&self.status());
+        if !self.succeeded {
+            row.add_css_class("error");
+        }
+        let mut details = vec![format!("$ {}", self.request)];
+        details.extend(self.outcomes.iter().cloned());
+        if !self.stderr.trim().is_empty() {
+            details.push(self.stderr.trim().to_string());
+        }
+        for text in details {
+            let label = Label::new(Some(&text));
+            label.set_xalign(0.0);
+            label.set_wrap(true);
+            label.set_selectable(true);
+            label.set_margin_start(12);
+            label.set_margin_end(12);
+            label.set_margin_top(6);
+            label.set_margin_bottom(6);
+            row.add_row(&label);
+        }
+        row
+    }
+}
+
+/// The log and the collapsible pane that shows it, newest first.
+pub struct ActivityLog {
+    entries: RefCell<Vec<Entry>>,
+    list: ListBox,
+    pub pane: Expander,
+    pub export: Button,
+}
+
+impl ActivityLog {
+    pub fn new() -> Self {
+        let list = ListBox::new();
+        list.set_selection_mode(gtk4::SelectionMode::None);
+        list.add_css_class("boxed-list");
+        let placeholder = Label::new(Some(&tr!("Nothing has been run yet.")));
+        placeholder.set_margin_top(12);
+        placeholder.set_margin_bottom(12);
+        placeholder.add_css_class("dim-label");
+        list.set_placeholder(Some(&placeholder));
+
+        let scroll = ScrolledWindow::new();
+        scroll.set_hscrollbar_policy(gtk4::PolicyType::Never);
+        scroll.set_min_content_height(180);
+        scroll.set_child(Some(&list));
+
+        let export = Button::with_label(&tr!("Export…"));
+        export.set_halign(gtk4::Align::End);
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.set_margin_top(6);
+        content.append(&scroll);
+        content.append(&export);
+
+        let pane = Expander::new(Some(&tr!("Activity")));
+        pane.set_margin_start(12);
+        pane.set_margin_end(12);
+        pane.set_margin_top(6);
+        pane.set_margin_bottom(6);
+        pane.set_child(Some(&content));
+
+        let entries = Self::load();
+        for entry in &entries {
+            list.prepend(&entry.row());
+        }
+        Self { entries: RefCell::new(entries), list, pane, export }
+    }
+
+    /// `$XDG_STATE_HOME/zelos/activity.json`, falling back to `~/.local/state`.
+    fn path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_STATE_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))?;
+        Some(base.join("zelos").join("activity.json"))
+    }
+
+    fn load() -> Vec<Entry> {
+        let Some(path) = Self::path() else {
+            return Vec::new();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                eprintln!("Ignoring {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("neither XDG_STATE_HOME nor HOME is set")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(&*self.entries.borrow()).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| e.to_string())
+    }
+
+    /// Runs `command`, which goes through pkexec, and logs it under
+    /// `action`. `outcomes` picks the per-setting results out of its output.
+    pub fn run(&self, action: &str, command: &mut Command, outcomes: impl Fn(&Output) -> Vec<String>) -> std::io::Result<Output> {
+        let mut argv = vec![command.get_program().to_string_lossy().to_string()];
+        argv.extend(command.get_args().map(|a| a.to_string_lossy().to_string()));
+        let output = command.output();
+        let entry = Entry {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            action: action.to_string(),
+            request: crate::init::shell_quote(&argv),
+            elevation: Elevation::of(&output),
+            succeeded: output.as_ref().is_ok_and(|out| out.status.success()),
+            outcomes: output.as_ref().map(&outcomes).unwrap_or_default(),
+            stderr: match &output {
+                Ok(out) => String::from_utf8_lossy(&out.stderr).trim().to_string(),
+                Err(e) => e.to_string(),
+            },
+        };
+        self.list.prepend(&entry.row());
+        {
+            let mut entries = self.entries.borrow_mut();
+            entries.push(entry);
+            if entries.len() > CAPACITY {
+                let excess = entries.len() - CAPACITY;
+                entries.drain(..excess);
+                // The oldest rows are at the bottom.
+                while let Some(row) = self.list.row_at_index(CAPACITY as i32) {
+                    self.list.remove(&row);
+                }
+            }
+        }
+        if let Err(e) = self.save() {
+            eprintln!("Failed to save the activity log: {}", e);
+        }
+        output
+    }
+
+    /// Writes the whole log as text, oldest first.
+    pub fn write_text(&self, path: &std::path::Path) -> Result<(), String> {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path).map_err(|e| e.to_string())?);
+        for entry in self.entries.borrow().iter() {
+            entry.write_text(&mut out).map_err(|e| e.to_string())?;
+        }
+        out.flush().map_err(|e| e.to_string())
+    }
+}
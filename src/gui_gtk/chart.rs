@@ -0,0 +1,870 @@
+// Time-series charts for the Metrics tab
+
+use gtk4::prelude::*;
+use gtk4::{cairo, Box as GtkBox, ComboBoxText, DrawingArea, Label, Orientation, ToggleButton};
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::i18n::{number, tr};
+use super::prefs::Units;
+
+/// Selectable chart windows, in seconds.
+const WINDOWS: [f64; 4] = [60.0, 300.0, 600.0, 1800.0];
+/// Window of the overview card and mini monitor sparklines, in seconds.
+pub const SPARKLINE_SECS: f64 = 60.0;
+/// Narrowest window scroll-zoom goes down to, in seconds.
+const MIN_WINDOW_SECS: f64 = 15.0;
+/// Samples kept per series (the widest window at the fastest sampling, 2 Hz).
+const CAPACITY: usize = 3600;
+
+/// A value sampled from every GPU at the sample interval.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Metric {
+    CoreClock,
+    MemClock,
+    Power,
+    Temperature,
+    Utilization,
+    FanSpeed,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 6] = [Metric::CoreClock, Metric::MemClock, Metric::Power, Metric::Temperature, Metric::Utilization, Metric::FanSpeed];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Metric::CoreClock => "coreClock",
+            Metric::MemClock => "memClock",
+            Metric::Power => "power",
+            Metric::Temperature => "temperature",
+            Metric::Utilization => "utilization",
+            Metric::FanSpeed => "fanSpeed",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.id() == id)
+    }
+
+    pub fn title(self) -> String {
+        match self {
+            Metric::CoreClock => tr!("Core Clock"),
+            Metric::MemClock => tr!("Memory Clock"),
+            Metric::Power => tr!("Power"),
+            Metric::Temperature => tr!("Temperature"),
+            Metric::Utilization => tr!("GPU Usage"),
+            Metric::FanSpeed => tr!("Fan Speed"),
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Metric::CoreClock | Metric::MemClock => "MHz",
+            Metric::Power => "W",
+            Metric::Temperature => "°C",
+            Metric::Utilization | Metric::FanSpeed => "%",
+        }
+    }
+
+    /// Default line color.
+    pub fn color(self) -> (f64, f64, f64) {
+        match self {
+            // Orange hue that remains readable in light and dark.
+            Metric::CoreClock => (0.95, 0.55, 0.20),
+            Metric::MemClock => (0.18, 0.53, 1.0),
+            Metric::Power => (0.90, 0.30, 0.35),
+            Metric::Temperature => (0.95, 0.75, 0.20),
+            Metric::Utilization => (0.35, 0.80, 0.45),
+            Metric::FanSpeed => (0.60, 0.45, 0.95),
+        }
+    }
+
+    /// Upper end of the axis for metrics with a natural maximum.
+    fn fixed_max(self) -> Option<f64> {
+        match self {
+            Metric::Utilization | Metric::FanSpeed => Some(100.0),
+            _ => None,
+        }
+    }
+}
+
+/// Something that happened to a GPU, marked on its charts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// Settings applied from the GUI
+    Applied,
+    /// Settings changed by something other than the GUI
+    External,
+    Profile,
+    Throttle,
+    Xid,
+}
+
+impl EventKind {
+    fn title(self) -> String {
+        match self {
+            EventKind::Applied => tr!("Settings applied"),
+            EventKind::External => tr!("Settings changed outside zelos"),
+            EventKind::Profile => tr!("Profile switched"),
+            EventKind::Throttle => tr!("Thermal throttling"),
+            EventKind::Xid => tr!("Xid error"),
+        }
+    }
+
+    fn color(self) -> (f64, f64, f64) {
+        match self {
+            EventKind::Applied => (0.35, 0.80, 0.45),
+            EventKind::External => (0.95, 0.75, 0.20),
+            EventKind::Profile => (0.60, 0.45, 0.95),
+            EventKind::Throttle => (0.95, 0.55, 0.20),
+            EventKind::Xid => (0.90, 0.30, 0.35),
+        }
+    }
+}
+
+pub struct Event {
+    /// Seconds since the chart start
+    pub t: f64,
+    pub kind: EventKind,
+    pub detail: String,
+}
+
+/// Events kept per GPU.
+const EVENT_CAPACITY: usize = 200;
+
+/// Samples of one metric. Time is seconds since the chart start.
+pub struct Series {
+    pub points: VecDeque<(f64, f64)>,
+}
+
+impl Series {
+    fn new() -> Self {
+        let mut points = VecDeque::with_capacity(CAPACITY);
+        // Assume x=0s and y=0 when plotting begins.
+        points.push_back((0.0, 0.0));
+        Self { points }
+    }
+
+    pub fn last(&self) -> Option<f64> {
+        self.points.back().map(|p| p.1)
+    }
+}
+
+/// Every metric sampled for one GPU.
+pub struct GpuHistory {
+    series: HashMap<Metric, Series>,
+    events: VecDeque<Event>,
+}
+
+impl GpuHistory {
+    pub fn new() -> Self {
+        Self { series: Metric::ALL.into_iter().map(|m| (m, Series::new())).collect(), events: VecDeque::new() }
+    }
+
+    pub fn mark(&mut self, kind: EventKind, t: f64, detail: String) {
+        self.events.push_back(Event { t, kind, detail });
+        while self.events.len() > EVENT_CAPACITY {
+            self.events.pop_front();
+        }
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    pub fn push(&mut self, metric: Metric, t: f64, v: f64) {
+        let series = self.series.entry(metric).or_insert_with(Series::new);
+        series.points.push_back((t, v));
+        while series.points.len() > CAPACITY {
+            series.points.pop_front();
+        }
+    }
+
+    pub fn series(&self, metric: Metric) -> Option<&Series> {
+        self.series.get(&metric)
+    }
+}
+
+/// Histories of every GPU, by index.
+pub type Histories = Rc<RefCell<HashMap<u32, GpuHistory>>>;
+
+/// Time range shared by every chart: how much history is shown and whether
+/// it follows the live samples or is held at a fixed end.
+pub struct ChartView {
+    /// Window selector and pause toggle, for the Metrics header
+    pub controls: GtkBox,
+    pause: ToggleButton,
+    start: Instant,
+    wall_start: SystemTime,
+    window: Cell<f64>,
+    /// End of the shown range while paused, in seconds since the start
+    end: Cell<Option<f64>>,
+    units: Cell<Units>,
+    /// Line colors that replace the metrics' defaults
+    colors: RefCell<HashMap<Metric, (f64, f64, f64)>>,
+}
+
+impl ChartView {
+    /// A live view of the last `window` seconds.
+    pub fn new(start: Instant, window: f64) -> Rc<Self> {
+        let window = window.clamp(MIN_WINDOW_SECS, WINDOWS[WINDOWS.len() - 1]);
+        let window_combo = ComboBoxText::new();
+        for secs in WINDOWS {
+            window_combo.append(Some(&secs.to_string()), &tr!("Last {} min", number(secs / 60.0, 0)));
+        }
+        window_combo.set_active_id(Some(&window.to_string()));
+        window_combo.set_valign(gtk4::Align::Center);
+
+        let pause = ToggleButton::new();
+        pause.set_icon_name("media-playback-pause-symbolic");
+        pause.set_tooltip_text(Some(&tr!("Pause charts")));
+        pause.set_valign(gtk4::Align::Center);
+
+        let controls = GtkBox::new(Orientation::Horizontal, 6);
+        controls.append(&window_combo);
+        controls.append(&pause);
+
+        let view = Rc::new(Self {
+            controls,
+            pause: pause.clone(),
+            start,
+            wall_start: SystemTime::now(),
+            window: Cell::new(window),
+            end: Cell::new(None),
+            units: Cell::new(Units::default()),
+            colors: RefCell::new(HashMap::new()),
+        });
+
+        {
+            let view = view.clone();
+            window_combo.connect_changed(move |combo| {
+                if let Some(secs) = combo.active_id().and_then(|id| id.parse().ok()) {
+                    view.window.set(secs);
+                }
+            });
+        }
+        {
+            let view = view.clone();
+            pause.connect_toggled(move |b| {
+                view.end.set(b.is_active().then(|| view.now()));
+            });
+        }
+        view
+    }
+
+    /// Seconds of history shown.
+    pub fn window_secs(&self) -> f64 {
+        self.window.get()
+    }
+
+    pub fn set_units(&self, units: Units) {
+        self.units.set(units);
+    }
+
+    pub fn set_colors(&self, colors: HashMap<Metric, (f64, f64, f64)>) {
+        *self.colors.borrow_mut() = colors;
+    }
+
+    fn color(&self, metric: Metric) -> (f64, f64, f64) {
+        self.colors.borrow().get(&metric).copied().unwrap_or_else(|| metric.color())
+    }
+
+    /// `v` (in the metric's own unit) in the unit the user picked.
+    fn display(&self, metric: Metric, v: f64) -> f64 {
+        match metric {
+            Metric::Power => self.units.get().power_value(v * 1000.0),
+            Metric::Temperature => self.units.get().temp_value(v),
+            _ => v,
+        }
+    }
+
+    fn unit(&self, metric: Metric) -> &'static str {
+        match metric {
+            Metric::Power => self.units.get().power_unit(),
+            Metric::Temperature => self.units.get().temp_unit(),
+            _ => metric.unit(),
+        }
+    }
+
+    /// Seconds since the chart start.
+    fn now(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    /// Shown time range `(start_t, end_t)`. The line fills left to right
+    /// from 0 s and starts scrolling once it reaches the end of the window.
+    fn range(&self) -> (f64, f64) {
+        let span = self.window.get() * 0.95;
+        let end = self.end.get().unwrap_or_else(|| self.now());
+        let start_t = (end - span).max(0.0);
+        (start_t, start_t + span)
+    }
+
+    /// Holds the range at `end`, pausing the charts if they were live.
+    fn pan_to(&self, end: f64) {
+        let span = self.window.get() * 0.95;
+        let now = self.now();
+        if !self.pause.is_active() {
+            self.pause.set_active(true);
+        }
+        self.end.set(Some(end.min(now).max(span.min(now))));
+    }
+
+    /// Zooms in (`factor` < 1) or out, keeping the end of the range.
+    fn zoom(&self, factor: f64) {
+        self.window.set((self.window.get() * factor).clamp(MIN_WINDOW_SECS, WINDOWS[WINDOWS.len() - 1]));
+    }
+
+    /// Wall-clock time of `t` seconds since the chart start.
+    fn wall_time(&self, t: f64) -> SystemTime {
+        self.wall_start + Duration::from_secs_f64(t.max(0.0))
+    }
+
+    /// Wall-clock time of `t` seconds since the chart start, as HH:MM:SS UTC.
+    fn timestamp(&self, t: f64) -> String {
+        let time = humantime::format_rfc3339_seconds(self.wall_time(t)).to_string();
+        time.get(11..19).unwrap_or(&time).to_string()
+    }
+}
+
+/// Draws the last `window_secs` of `points` (seconds since `now_t`'s origin,
+/// any unit) as a small line chart without axes, for the overview cards and
+/// the mini monitor.
+pub fn draw_sparkline(cr: &cairo::Context, w: f64, h: f64, points: &VecDeque<(f64, f64)>, now_t: f64, window_secs: f64) {
+    let pad = 4.0;
+    let plot_w = (w - 2.0 * pad).max(1.0);
+    let plot_h = (h - 2.0 * pad).max(1.0);
+    let start_t = (now_t - window_secs).max(0.0);
+
+    let max_v = points.iter().map(|p| p.1).filter(|v| v.is_finite()).fold(0.0_f64, f64::max);
+    let max_v = if max_v <= 0.0 { 1.0 } else { max_v * 1.08 };
+
+    cr.set_source_rgba(1.0, 1.0, 1.0, 0.06);
+    cr.set_line_width(1.0);
+    cr.move_to(pad, pad + plot_h);
+    cr.line_to(pad + plot_w, pad + plot_h);
+    let _ = cr.stroke();
+
+    cr.set_line_width(1.5);
+    cr.set_line_join(cairo::LineJoin::Round);
+    cr.set_source_rgba(0.95, 0.55, 0.20, 1.0);
+    let mut started = false;
+    for &(t, v) in points.iter().filter(|p| p.0 >= start_t) {
+        let x = pad + ((t - start_t) / window_secs).clamp(0.0, 1.0) * plot_w;
+        let y = pad + (1.0 - (v / max_v).clamp(0.0, 1.0)) * plot_h;
+        if started {
+            cr.line_to(x, y);
+        } else {
+            cr.move_to(x, y);
+            started = true;
+        }
+    }
+    let _ = cr.stroke();
+}
+
+/// A GPU as identified in exported history.
+pub struct GpuIdentity {
+    pub index: u32,
+    pub name: String,
+    pub uuid: String,
+}
+
+/// Quotes a CSV field if it needs it.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Writes every sample in `histories` as CSV, one row per sample.
+pub fn write_csv(out: &mut impl Write, histories: &HashMap<u32, GpuHistory>, gpus: &[GpuIdentity], view: &ChartView) -> io::Result<()> {
+    write_samples(out, histories, gpus, |t| view.wall_time(t))
+}
+
+/// [`write_csv`], with `wall_time` giving the wall-clock time of a sample.
+/// Numbers are written without locale separators, to stay machine-readable.
+fn write_samples(out: &mut impl Write, histories: &HashMap<u32, GpuHistory>, gpus: &[GpuIdentity], wall_time: impl Fn(f64) -> SystemTime) -> io::Result<()> {
+    writeln!(out, "time,elapsed_s,gpu_index,gpu_name,gpu_uuid,metric,unit,value")?;
+    for gpu in gpus {
+        let Some(history) = histories.get(&gpu.index) else {
+            continue;
+        };
+        for metric in Metric::ALL {
+            let Some(series) = history.series(metric) else {
+                continue;
+            };
+            // Skip the placeholder every series starts with.
+            for &(t, v) in series.points.iter().filter(|p| p.0 > 0.0) {
+                writeln!(
+                    out,
+                    "{},{:.3},{},{},{},{},{},{}",
+                    humantime::format_rfc3339_millis(wall_time(t)),
+                    t,
+                    gpu.index,
+                    csv_field(&gpu.name),
+                    csv_field(&gpu.uuid),
+                    metric.id(),
+                    metric.unit(),
+                    v
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Value range of the axis for `metric`, anchored at 0 with some headroom.
+fn axis_max(metric: Metric, values: impl Iterator<Item = f64>) -> f64 {
+    if let Some(max) = metric.fixed_max() {
+        return max;
+    }
+    let max_v = values.filter(|v| v.is_finite()).fold(0.0_f64, f64::max);
+    if max_v <= 0.0 {
+        1.0
+    } else {
+        max_v + max_v * 0.08
+    }
+}
+
+/// A card with a line chart of one metric of the selected GPU, optionally
+/// overlaid with a second metric on its own axis.
+#[derive(Clone)]
+pub struct TimeChart {
+    pub card: GtkBox,
+    area: DrawingArea,
+    value: Label,
+    metric: Metric,
+    overlay: Rc<Cell<Option<Metric>>>,
+    histories: Histories,
+    gpu: Rc<Cell<u32>>,
+    view: Rc<ChartView>,
+    /// Pointer x position while hovering the plot
+    hover: Rc<Cell<Option<f64>>>,
+}
+
+/// Plot area inside the chart's padding.
+const PAD: f64 = 12.0;
+
+/// Width of the plot the range is drawn over; the last 5% is left empty.
+fn usable_width(w: f64) -> f64 {
+    (w - 2.0 * PAD).max(1.0) * 0.95
+}
+
+impl TimeChart {
+    /// A chart of `metric` for the GPU in `gpu`, drawn from `histories` over
+    /// the range of `view`.
+    pub fn new(metric: Metric, histories: Histories, gpu: Rc<Cell<u32>>, view: Rc<ChartView>) -> Self {
+        let card = GtkBox::new(Orientation::Vertical, 10);
+        card.set_css_classes(&["card", "metrics-card"]);
+        card.set_hexpand(true);
+        card.set_vexpand(true);
+        card.set_valign(gtk4::Align::Fill);
+
+        let title = Label::new(Some(&tr!("{} History", metric.title())));
+        title.set_halign(gtk4::Align::Start);
+        title.set_hexpand(true);
+        title.set_css_classes(&["metrics-title"]);
+
+        // Second metric drawn against the right axis.
+        let overlay_combo = ComboBoxText::new();
+        overlay_combo.append(Some(""), &tr!("No overlay"));
+        for m in Metric::ALL.into_iter().filter(|&m| m != metric) {
+            overlay_combo.append(Some(m.id()), &tr!("vs. {}", m.title()));
+        }
+        overlay_combo.set_active_id(Some(""));
+        overlay_combo.set_valign(gtk4::Align::Center);
+
+        let header = GtkBox::new(Orientation::Horizontal, 12);
+        header.set_hexpand(true);
+        header.append(&title);
+        header.append(&overlay_combo);
+        card.append(&header);
+
+        let area = DrawingArea::new();
+        area.set_hexpand(true);
+        area.set_vexpand(true);
+        area.set_content_height(190);
+        // Keep the overall window from expanding; allow the chart to shrink.
+        area.set_content_width(250);
+        card.append(&area);
+
+        // Bottom-right: latest value
+        let value = Label::new(Some(&tr!("N/A")));
+        value.set_halign(gtk4::Align::End);
+        value.set_css_classes(&["metrics-stat-value"]);
+        // Keep width stable as values update (prevents window width changes).
+        value.set_width_chars(10);
+        card.append(&value);
+
+        let chart = Self { card, area, value, metric, overlay: Rc::new(Cell::new(None)), histories, gpu, view, hover: Rc::new(Cell::new(None)) };
+
+        {
+            let chart = chart.clone();
+            overlay_combo.connect_changed(move |combo| {
+                chart.overlay.set(combo.active_id().and_then(|id| Metric::from_id(&id)));
+                chart.refresh();
+                chart.area.queue_draw();
+            });
+        }
+        {
+            let chart_cl = chart.clone();
+            chart.area.set_draw_func(move |_, cr, w, h| chart_cl.draw(cr, w as f64, h as f64));
+        }
+
+        // Scroll to zoom the time range
+        let scroll = gtk4::EventControllerScroll::new(gtk4::EventControllerScrollFlags::VERTICAL);
+        {
+            let chart = chart.clone();
+            scroll.connect_scroll(move |_, _, dy| {
+                chart.view.zoom(1.25_f64.powf(dy));
+                chart.area.queue_draw();
+                gtk4::Inhibit(true)
+            });
+        }
+        chart.area.add_controller(scroll);
+
+        // Drag to pan back through the history; this pauses the charts
+        let drag = gtk4::GestureDrag::new();
+        let drag_end = Rc::new(Cell::new(0.0));
+        {
+            let chart = chart.clone();
+            let drag_end = drag_end.clone();
+            drag.connect_drag_begin(move |_, _, _| {
+                drag_end.set(chart.view.range().1);
+            });
+        }
+        {
+            let chart = chart.clone();
+            drag.connect_drag_update(move |_, dx, _| {
+                let secs_per_px = chart.view.window.get() * 0.95 / usable_width(chart.area.width() as f64);
+                chart.view.pan_to(drag_end.get() - dx * secs_per_px);
+                chart.area.queue_draw();
+            });
+        }
+        chart.area.add_controller(drag);
+
+        // Hover for the exact value and time of a sample
+        let motion = gtk4::EventControllerMotion::new();
+        {
+            let chart = chart.clone();
+            motion.connect_motion(move |_, x, _| {
+                chart.hover.set(Some(x));
+                chart.area.queue_draw();
+            });
+        }
+        {
+            let chart = chart.clone();
+            motion.connect_leave(move |_| {
+                chart.hover.set(None);
+                chart.area.queue_draw();
+            });
+        }
+        chart.area.add_controller(motion);
+        chart.area.set_has_tooltip(true);
+        {
+            let chart_cl = chart.clone();
+            chart.area.connect_query_tooltip(move |area, x, _, _, tooltip| match chart_cl.tooltip_text(x as f64, area.width() as f64) {
+                Some(text) => {
+                    tooltip.set_text(Some(&text));
+                    true
+                }
+                None => false,
+            });
+        }
+        chart
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Renders the chart as it's currently shown to a `width`×`height` PNG.
+    pub fn save_png(&self, path: &std::path::Path, width: i32, height: i32) -> Result<(), String> {
+        let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).map_err(|e| e.to_string())?;
+        {
+            let cr = cairo::Context::new(&surface).map_err(|e| e.to_string())?;
+            // The chart is drawn for a dark card, so give it one.
+            cr.set_source_rgb(0.14, 0.14, 0.15);
+            cr.paint().map_err(|e| e.to_string())?;
+            // Leave the hover cursor out of the export.
+            let hover = self.hover.take();
+            self.draw(&cr, width as f64, height as f64);
+            self.hover.set(hover);
+        }
+        surface.flush();
+        let stride = surface.stride() as usize;
+        let data = surface.data().map_err(|e| e.to_string())?.to_vec();
+        // Cairo's ARGB32 is a premultiplied native-endian word, so BGRA in
+        // memory on little-endian and ARGB on big-endian.
+        let format = if cfg!(target_endian = "little") { gtk4::gdk::MemoryFormat::B8g8r8a8Premultiplied } else { gtk4::gdk::MemoryFormat::A8r8g8b8Premultiplied };
+        let texture = gtk4::gdk::MemoryTexture::new(width, height, format, &gtk4::glib::Bytes::from_owned(data), stride);
+        texture.save_to_png(path).map_err(|e| e.to_string())
+    }
+
+    pub fn queue_draw(&self) {
+        self.area.queue_draw();
+    }
+
+    /// Updates the latest-value readout from the history.
+    pub fn refresh(&self) {
+        let histories = self.histories.borrow();
+        let history = histories.get(&self.gpu.get());
+        let format = |metric: Metric| {
+            history
+                .and_then(|h| h.series(metric))
+                .and_then(Series::last)
+                .map(|v| format!("{} {}", number(self.view.display(metric, v), 0), self.view.unit(metric)))
+                .unwrap_or_else(|| tr!("N/A"))
+        };
+        match self.overlay.get() {
+            Some(overlay) => self.value.set_text(&format!("{} · {}", format(self.metric), format(overlay))),
+            None => self.value.set_text(&format(self.metric)),
+        }
+    }
+
+    /// Sample time under pointer position `x`, if it's inside the range.
+    fn time_at(&self, x: f64, w: f64) -> Option<f64> {
+        let (start_t, end_t) = self.view.range();
+        let t = start_t + (x - PAD) / usable_width(w) * (end_t - start_t);
+        (t >= start_t && t <= end_t.min(self.view.now())).then_some(t)
+    }
+
+    /// Sample of `metric` closest to time `t`.
+    fn sample_near(&self, metric: Metric, t: f64) -> Option<(f64, f64)> {
+        let histories = self.histories.borrow();
+        let series = histories.get(&self.gpu.get())?.series(metric)?;
+        series.points.iter().copied().min_by(|a, b| (a.0 - t).abs().total_cmp(&(b.0 - t).abs()))
+    }
+
+    /// Details of the events drawn within a few pixels of `x`.
+    fn events_near(&self, x: f64, w: f64) -> Vec<String> {
+        let (start_t, end_t) = self.view.range();
+        let secs_per_px = (end_t - start_t) / usable_width(w);
+        let t = start_t + (x - PAD) * secs_per_px;
+        let histories = self.histories.borrow();
+        let Some(history) = histories.get(&self.gpu.get()) else {
+            return Vec::new();
+        };
+        history
+            .events()
+            .filter(|e| e.t >= start_t && e.t <= end_t && (e.t - t).abs() <= 4.0 * secs_per_px)
+            .map(|e| tr!("{} at {} UTC: {}", e.kind.title(), self.view.timestamp(e.t), e.detail))
+            .collect()
+    }
+
+    fn tooltip_text(&self, x: f64, w: f64) -> Option<String> {
+        let events = self.events_near(x, w);
+        if !events.is_empty() {
+            return Some(events.join("\n"));
+        }
+        let t = self.time_at(x, w)?;
+        let (sample_t, v) = self.sample_near(self.metric, t)?;
+        let mut text = format!("{}: {} {}", self.metric.title(), number(self.view.display(self.metric, v), 0), self.view.unit(self.metric));
+        if let Some(overlay) = self.overlay.get() {
+            if let Some((_, ov)) = self.sample_near(overlay, t) {
+                text.push_str(&format!("\n{}: {} {}", overlay.title(), number(self.view.display(overlay, ov), 0), self.view.unit(overlay)));
+            }
+        }
+        text.push('\n');
+        text.push_str(&tr!("{} UTC ({} s ago)", self.view.timestamp(sample_t), number(self.view.now() - sample_t, 0)));
+        Some(text)
+    }
+
+    fn draw(&self, cr: &cairo::Context, w: f64, h: f64) {
+        let pad = PAD;
+        let label_pad = 16.0;
+        let plot_w = (w - 2.0 * pad).max(1.0);
+        let plot_h = (h - 2.0 * pad).max(1.0);
+
+        let now_t = self.view.now();
+        let (start_t, end_t) = self.view.range();
+        let fill_span = end_t - start_t;
+        let usable_w = usable_width(w);
+
+        let overlay = self.overlay.get();
+
+        // Axis labels (Y only), rotated and centered on their side.
+        cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+        cr.set_font_size(12.0);
+        let y_label = |metric: Metric, x: f64, angle: f64, offset: f64| {
+            cr.set_source_rgba(1.0, 1.0, 1.0, 0.55);
+            cr.save().ok();
+            cr.translate(x, h / 2.0);
+            cr.rotate(angle);
+            let label = format!("{} ({})", metric.title(), self.view.unit(metric));
+            let (ext_w, ext_xb) = match cr.text_extents(&label) {
+                Ok(ext) => (ext.width(), ext.x_bearing()),
+                Err(_) => (0.0, 0.0),
+            };
+            cr.move_to(-ext_w / 2.0 - ext_xb, offset);
+            let _ = cr.show_text(&label);
+            cr.restore().ok();
+        };
+        y_label(self.metric, 10.0, -std::f64::consts::FRAC_PI_2, -label_pad);
+        if let Some(overlay) = overlay {
+            y_label(overlay, w - 10.0, std::f64::consts::FRAC_PI_2, -label_pad);
+        }
+
+        let histories = self.histories.borrow();
+        let Some(history) = histories.get(&self.gpu.get()) else {
+            return;
+        };
+        let Some(primary) = history.series(self.metric).filter(|s| !s.points.is_empty()) else {
+            return;
+        };
+        let max_v = axis_max(self.metric, primary.points.iter().map(|p| self.view.display(self.metric, p.1)));
+        let overlay_series = overlay.and_then(|m| Some((m, history.series(m)?))).filter(|(_, s)| !s.points.is_empty());
+        let overlay_max = overlay_series.map(|(m, s)| axis_max(m, s.points.iter().map(|p| self.view.display(m, p.1))));
+
+        // Grid + Y ticks (primary on the left, overlay on the right)
+        cr.set_line_width(1.0);
+        for i in 0..=4 {
+            let t = (i as f64) / 4.0;
+            let y = pad + plot_h * t;
+
+            cr.set_source_rgba(1.0, 1.0, 1.0, 0.06);
+            cr.move_to(pad, y);
+            cr.line_to(pad + plot_w, y);
+            let _ = cr.stroke();
+
+            cr.set_font_size(11.0);
+            cr.set_source_rgba(1.0, 1.0, 1.0, 0.45);
+            cr.move_to(pad + 4.0, y - 2.0);
+            let _ = cr.show_text(&number(max_v * (1.0 - t), 0));
+            if let (Some((m, _)), Some(om)) = (overlay_series, overlay_max) {
+                let (r, g, b) = self.view.color(m);
+                cr.set_source_rgba(r, g, b, 0.7);
+                let text = number(om * (1.0 - t), 0);
+                let ext_w = cr.text_extents(&text).map(|e| e.width()).unwrap_or(0.0);
+                cr.move_to(pad + plot_w - ext_w - 4.0, y - 2.0);
+                let _ = cr.show_text(&text);
+            }
+        }
+
+        // Axes at (0,0)
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.12);
+        cr.set_line_width(1.0);
+        cr.move_to(pad, pad);
+        cr.line_to(pad, pad + plot_h);
+        let y0 = pad + plot_h;
+        cr.move_to(pad, y0);
+        cr.line_to(pad + usable_w, y0);
+        let _ = cr.stroke();
+
+        // Event markers: a dashed line with a tab at the top
+        cr.set_line_width(1.5);
+        for event in history.events().filter(|e| e.t >= start_t && e.t <= end_t) {
+            let x = pad + (event.t - start_t) / fill_span * usable_w;
+            let (r, g, b) = event.kind.color();
+            cr.set_source_rgba(r, g, b, 0.8);
+            cr.set_dash(&[4.0, 3.0], 0.0);
+            cr.move_to(x, pad);
+            cr.line_to(x, pad + plot_h);
+            let _ = cr.stroke();
+            cr.set_dash(&[], 0.0);
+            cr.move_to(x - 4.0, pad);
+            cr.line_to(x + 4.0, pad);
+            cr.line_to(x, pad + 6.0);
+            cr.close_path();
+            let _ = cr.fill();
+        }
+
+        let line = |metric: Metric, series: &Series, max_v: f64| {
+            cr.set_line_width(2.0);
+            cr.set_line_cap(cairo::LineCap::Round);
+            cr.set_line_join(cairo::LineJoin::Round);
+            let (r, g, b) = self.view.color(metric);
+            cr.set_source_rgba(r, g, b, 1.0);
+
+            let denom = max_v.max(1e-9_f64);
+            let to_xy = |t: f64, v: f64| {
+                let x = pad + ((t - start_t) / fill_span).clamp(0.0, 1.0) * usable_w;
+                let y = pad + (1.0 - (v / denom).clamp(0.0, 1.0)) * plot_h;
+                (x, y)
+            };
+            let mut started = false;
+            for &(t, v) in series.points.iter() {
+                if t < start_t {
+                    continue;
+                }
+                if t > end_t {
+                    break;
+                }
+                let (x, y) = to_xy(t, self.view.display(metric, v));
+                if !started {
+                    cr.move_to(x, y);
+                    started = true;
+                } else {
+                    cr.line_to(x, y);
+                }
+            }
+
+            // Extend to "now" so the line visually grows between 1Hz samples.
+            let t_now = now_t.min(end_t);
+            if t_now >= start_t {
+                let (x, y) = to_xy(t_now, self.view.display(metric, series.last().unwrap_or(0.0)));
+                if !started {
+                    cr.move_to(x, y);
+                } else {
+                    cr.line_to(x, y);
+                }
+            }
+            let _ = cr.stroke();
+        };
+        if let (Some((m, s)), Some(om)) = (overlay_series, overlay_max) {
+            line(m, s, om);
+        }
+        line(self.metric, primary, max_v);
+
+        // Hover cursor
+        if let Some(x) = self.hover.get().filter(|&x| self.time_at(x, w).is_some()) {
+            cr.set_source_rgba(1.0, 1.0, 1.0, 0.35);
+            cr.set_line_width(1.0);
+            cr.move_to(x, pad);
+            cr.line_to(x, pad + plot_h);
+            let _ = cr.stroke();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_export() {
+        let mut history = GpuHistory::new();
+        history.push(Metric::CoreClock, 1.5, 1905.0);
+        history.push(Metric::Power, 2.0, 1234.567);
+        let histories = HashMap::from([(0, history)]);
+        let gpus = [GpuIdentity { index: 0, name: "NVIDIA GeForce RTX 4090, \"OC\"".to_string(), uuid: "GPU-1234".to_string() }];
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let mut out = Vec::new();
+        write_samples(&mut out, &histories, &gpus, |t| start + Duration::from_secs_f64(t)).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "time,elapsed_s,gpu_index,gpu_name,gpu_uuid,metric,unit,value",
+                "2023-11-14T22:13:21.500Z,1.500,0,\"NVIDIA GeForce RTX 4090, \"\"OC\"\"\",GPU-1234,coreClock,MHz,1905",
+                "2023-11-14T22:13:22.000Z,2.000,0,\"NVIDIA GeForce RTX 4090, \"\"OC\"\"\",GPU-1234,power,W,1234.567",
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
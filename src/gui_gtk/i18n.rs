@@ -0,0 +1,149 @@
+// Translations and locale-aware number formatting for the GUI
+
+use std::ffi::CStr;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Text domain of the catalogs in `po/`.
+const DOMAIN: &str = "zelos";
+/// Where compiled catalogs are looked up. Set `ZELOS_LOCALEDIR` when
+/// building to install them elsewhere.
+const LOCALEDIR: &str = match option_env!("ZELOS_LOCALEDIR") {
+    Some(dir) => dir,
+    None => "/usr/share/locale",
+};
+
+/// Picks up the user's locale and binds the zelos catalogs. Untranslated
+/// strings, or a missing catalog, fall back to English.
+pub fn init() {
+    gettextrs::setlocale(gettextrs::LocaleCategory::LcAll, "");
+    let bound = gettextrs::bindtextdomain(DOMAIN, LOCALEDIR)
+        .and_then(|_| gettextrs::bind_textdomain_codeset(DOMAIN, "UTF-8"))
+        .and_then(|_| gettextrs::textdomain(DOMAIN));
+    if let Err(e) = bound {
+        eprintln!("Failed to load translations: {}", e);
+    }
+}
+
+/// Fills the `{}` placeholders of an already translated `text` with `args`,
+/// in order. Placeholders without an argument are left as they are, and
+/// arguments without a placeholder are dropped.
+pub fn fill(text: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut args = args.iter();
+    let mut parts = text.split("{}");
+    if let Some(first) = parts.next() {
+        out.push_str(first);
+    }
+    for part in parts {
+        match args.next() {
+            Some(arg) => out.push_str(&arg.to_string()),
+            None => out.push_str("{}"),
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+/// Translates a message. With arguments, its `{}` placeholders are filled
+/// in order, like `format!` without format specs; format numbers with
+/// [`number`] first.
+macro_rules! tr {
+    ($msgid:literal) => {
+        gettextrs::gettext($msgid)
+    };
+    ($msgid:literal, $($arg:expr),+ $(,)?) => {
+        $crate::gui_gtk::i18n::fill(&gettextrs::gettext($msgid), &[$(&$arg),+])
+    };
+}
+pub(super) use tr;
+
+/// The locale's decimal point and thousands separator.
+fn separators() -> &'static (String, String) {
+    static SEPARATORS: OnceLock<(String, String)> = OnceLock::new();
+    SEPARATORS.get_or_init(|| {
+        // SAFETY: localeconv returns a pointer to static data (or null) that
+        // stays valid until the locale changes, which only `init` does
+        // before any number is formatted.
+        unsafe {
+            let lc = libc::localeconv();
+            if lc.is_null() {
+                return (".".to_string(), String::new());
+            }
+            let field = |p: *const libc::c_char| if p.is_null() { String::new() } else { CStr::from_ptr(p).to_string_lossy().into_owned() };
+            let point = field((*lc).decimal_point);
+            (if point.is_empty() { ".".to_string() } else { point }, field((*lc).thousands_sep))
+        }
+    })
+}
+
+/// `v` with `decimals` decimal places, using the locale's decimal point and
+/// grouping thousands with its separator.
+pub fn number(v: f64, decimals: usize) -> String {
+    let (point, thousands) = separators();
+    format_number(v, decimals, point, thousands)
+}
+
+fn format_number(v: f64, decimals: usize, point: &str, thousands: &str) -> String {
+    let text = format!("{:.*}", decimals, v.abs());
+    let (int, frac) = text.split_once('.').unwrap_or((&text, ""));
+    let mut out = String::new();
+    if v < 0.0 && text.chars().any(|c| c != '0' && c != '.') {
+        out.push('-');
+    }
+    for (i, digit) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            out.push_str(thousands);
+        }
+        out.push(digit);
+    }
+    if !frac.is_empty() {
+        out.push_str(point);
+        out.push_str(frac);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_placeholders_in_order() {
+        assert_eq!(fill("GPU {}: {}", &[&0, &"hot"]), "GPU 0: hot");
+        assert_eq!(fill("{}{}", &[&1, &2]), "12");
+        assert_eq!(fill("No placeholders", &[]), "No placeholders");
+    }
+
+    #[test]
+    fn fill_with_mismatched_arguments() {
+        // A translation that lost an argument still shows where it went.
+        assert_eq!(fill("{} of {} failed", &[&1]), "1 of {} failed");
+        assert_eq!(fill("{} failed", &[&1, &2]), "1 failed");
+    }
+
+    #[test]
+    fn groups_thousands() {
+        assert_eq!(format_number(0.0, 0, ".", ","), "0");
+        assert_eq!(format_number(999.0, 0, ".", ","), "999");
+        assert_eq!(format_number(1000.0, 0, ".", ","), "1,000");
+        assert_eq!(format_number(1234567.891, 2, ",", "."), "1.234.567,89");
+        assert_eq!(format_number(12345.0, 0, ".", ""), "12345");
+        assert_eq!(format_number(12345.0, 0, ",", "\u{202f}"), "12\u{202f}345");
+    }
+
+    #[test]
+    fn negative_values() {
+        assert_eq!(format_number(-1234.5, 1, ".", ","), "-1,234.5");
+        assert_eq!(format_number(-123.0, 0, ".", ","), "-123");
+        // Rounded to zero, so no sign.
+        assert_eq!(format_number(-0.04, 1, ".", ","), "0.0");
+    }
+
+    #[test]
+    fn rounding_across_a_group_boundary() {
+        assert_eq!(format_number(999.96, 1, ".", ","), "1,000.0");
+        assert_eq!(format_number(999.6, 0, ".", ","), "1,000");
+        assert_eq!(format_number(-999_999.7, 0, ",", "."), "-1.000.000");
+    }
+}
@@ -0,0 +1,166 @@
+// A small window with the main readouts of one GPU
+
+use adw::prelude::*;
+use gtk4::{Box as GtkBox, DrawingArea, Grid, Label, Orientation};
+use libadwaita as adw;
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Instant;
+
+use super::chart::{draw_sparkline, Histories, Metric, SPARKLINE_SECS};
+use super::i18n::{number, tr};
+use super::prefs::{Preferences, Units};
+use super::sampler::GpuSample;
+
+/// Core clock, memory clock, temperature, power and usage of the selected
+/// GPU, fed by the same samples as the Metrics tab. It's a window of its
+/// own so it can be moved to another monitor; keeping it above others is
+/// left to the window manager, as GTK 4 has no way to ask for it. It isn't
+/// an application window, so it closes with the main window.
+pub struct MiniMonitor {
+    window: adw::Window,
+    title: adw::WindowTitle,
+    core: Label,
+    mem: Label,
+    temp: Label,
+    power: Label,
+    usage: Label,
+    sparkline: DrawingArea,
+    gpu: Rc<Cell<u32>>,
+    gpus: Vec<(String, String)>,
+    prefs: Rc<RefCell<Preferences>>,
+}
+
+impl MiniMonitor {
+    pub fn new(prefs: Rc<RefCell<Preferences>>, histories: Histories, gpu: Rc<Cell<u32>>, gpus: Vec<(String, String)>, start: Instant) -> Self {
+        let window = adw::Window::new();
+        window.set_title(Some("zelos"));
+        window.set_default_size(240, -1);
+        window.set_hide_on_close(true);
+        window.set_opacity(prefs.borrow().mini_monitor.opacity.clamp(0.3, 1.0));
+        {
+            let prefs = prefs.clone();
+            window.connect_close_request(move |_| {
+                prefs.borrow_mut().mini_monitor.open = false;
+                gtk4::Inhibit(false)
+            });
+        }
+
+        let title = adw::WindowTitle::new("zelos", "");
+        // Said where it's noticed, since nothing in the window can do it.
+        title.set_tooltip_text(Some(&tr!("GTK 4 can't keep this window on top of others. Use your window manager's \"Always on Top\" for that.")));
+        let header = adw::HeaderBar::new();
+        header.set_title_widget(Some(&title));
+        header.add_css_class("flat");
+
+        let grid = Grid::new();
+        grid.set_row_spacing(4);
+        grid.set_column_spacing(12);
+        let readout = |row: i32, name: &str| {
+            let name_lbl = Label::new(Some(name));
+            name_lbl.set_halign(gtk4::Align::Start);
+            name_lbl.set_css_classes(&["metrics-stat-name"]);
+            let value = Label::new(Some(&tr!("N/A")));
+            value.set_halign(gtk4::Align::End);
+            value.set_hexpand(true);
+            value.set_css_classes(&["metrics-stat-value"]);
+            grid.attach(&name_lbl, 0, row, 1, 1);
+            grid.attach(&value, 1, row, 1, 1);
+            value
+        };
+        let core = readout(0, &tr!("Core"));
+        let mem = readout(1, &tr!("Memory"));
+        let temp = readout(2, &tr!("Temperature"));
+        let power = readout(3, &tr!("Power"));
+        let usage = readout(4, &tr!("Usage"));
+
+        let sparkline = DrawingArea::new();
+        sparkline.set_content_height(48);
+        sparkline.set_visible(prefs.borrow().mini_monitor.sparkline);
+        {
+            let histories = histories.clone();
+            let gpu = gpu.clone();
+            sparkline.set_draw_func(move |_, cr, w, h| {
+                if let Some(series) = histories.borrow().get(&gpu.get()).and_then(|h| h.series(Metric::CoreClock)) {
+                    draw_sparkline(cr, w as f64, h as f64, &series.points, start.elapsed().as_secs_f64(), SPARKLINE_SECS);
+                }
+            });
+        }
+
+        // Display options, kept in the preferences.
+        let sparkline_check = gtk4::CheckButton::with_label(&tr!("Core clock history"));
+        sparkline_check.set_active(prefs.borrow().mini_monitor.sparkline);
+        {
+            let prefs = prefs.clone();
+            let sparkline = sparkline.clone();
+            sparkline_check.connect_toggled(move |c| {
+                prefs.borrow_mut().mini_monitor.sparkline = c.is_active();
+                sparkline.set_visible(c.is_active());
+            });
+        }
+        let opacity_lbl = Label::new(Some(&tr!("Opacity")));
+        opacity_lbl.set_halign(gtk4::Align::Start);
+        let opacity = gtk4::Scale::with_range(Orientation::Horizontal, 0.3, 1.0, 0.05);
+        opacity.set_value(prefs.borrow().mini_monitor.opacity.clamp(0.3, 1.0));
+        opacity.set_width_request(160);
+        {
+            let prefs = prefs.clone();
+            let window = window.clone();
+            opacity.connect_value_changed(move |s| {
+                prefs.borrow_mut().mini_monitor.opacity = s.value();
+                window.set_opacity(s.value());
+            });
+        }
+        let options = GtkBox::new(Orientation::Vertical, 6);
+        options.set_margin_top(6);
+        options.set_margin_bottom(6);
+        options.set_margin_start(6);
+        options.set_margin_end(6);
+        options.append(&sparkline_check);
+        options.append(&opacity_lbl);
+        options.append(&opacity);
+        let options_popover = gtk4::Popover::new();
+        options_popover.set_child(Some(&options));
+        let options_btn = gtk4::MenuButton::new();
+        options_btn.set_icon_name("view-more-symbolic");
+        options_btn.set_tooltip_text(Some(&tr!("Display Options")));
+        options_btn.set_popover(Some(&options_popover));
+        header.pack_end(&options_btn);
+
+        let body = GtkBox::new(Orientation::Vertical, 8);
+        body.set_margin_start(12);
+        body.set_margin_end(12);
+        body.set_margin_bottom(12);
+        body.append(&grid);
+        body.append(&sparkline);
+
+        let content = GtkBox::new(Orientation::Vertical, 0);
+        content.append(&header);
+        content.append(&body);
+        window.set_content(Some(&content));
+
+        Self { window, title, core, mem, temp, power, usage, sparkline, gpu, gpus, prefs }
+    }
+
+    pub fn present(&self) {
+        self.prefs.borrow_mut().mini_monitor.open = true;
+        self.window.present();
+    }
+
+    /// Shows `sample` if it's from the selected GPU.
+    pub fn update(&self, sample: &GpuSample, units: Units) {
+        if sample.index != self.gpu.get() || !self.window.is_visible() {
+            return;
+        }
+        let label = self.gpus.iter().find(|(id, _)| id.parse() == Ok(sample.index)).map(|(_, label)| label.clone());
+        self.title.set_subtitle(&label.unwrap_or_else(|| tr!("GPU {}", sample.index)));
+        let or_na = |v: Option<String>| v.unwrap_or_else(|| tr!("N/A"));
+        self.core.set_text(&or_na(sample.core_clock.map(|c| tr!("{} MHz", number(c as f64, 0)))));
+        self.mem.set_text(&or_na(sample.mem_clock.map(|c| tr!("{} MHz", number(c as f64, 0)))));
+        self.temp.set_text(&or_na(sample.temp.map(|t| units.temp(t))));
+        self.power.set_text(&or_na(sample.power.map(|p| units.power(p))));
+        self.usage.set_text(&or_na(sample.utilization.map(|u| tr!("{} %", u))));
+        self.sparkline.queue_draw();
+    }
+}
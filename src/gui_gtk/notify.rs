@@ -0,0 +1,122 @@
+// Desktop notifications through org.freedesktop.Notifications
+
+use gtk4::prelude::*;
+use gtk4::{gio, glib};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use super::i18n::tr;
+use super::prefs::Preferences;
+use super::sampler::GpuSample;
+
+/// A condition that has to hold for a while before it's reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Sustained {
+    Temperature,
+    Power,
+}
+
+/// Sends alerts for the thresholds in the preferences, at most once per
+/// repeat interval for each kind of alert and GPU.
+pub struct Notifier {
+    connection: Option<gio::DBusConnection>,
+    prefs: Rc<RefCell<Preferences>>,
+    last_sent: RefCell<HashMap<String, Instant>>,
+    /// When each sustained condition began (seconds since the chart start)
+    /// and whether it has been reported
+    over: RefCell<HashMap<(u32, Sustained), (f64, bool)>>,
+}
+
+impl Notifier {
+    pub fn new(prefs: Rc<RefCell<Preferences>>) -> Self {
+        let connection = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>).ok();
+        Self { connection, prefs, last_sent: RefCell::new(HashMap::new()), over: RefCell::new(HashMap::new()) }
+    }
+
+    /// Sends a notification unless one with the same `key` went out within
+    /// the repeat interval.
+    fn send(&self, key: &str, summary: &str, body: &str, critical: bool) {
+        let Some(ref connection) = self.connection else {
+            return;
+        };
+        let repeat = Duration::from_secs(self.prefs.borrow().notifications.repeat_mins as u64 * 60);
+        {
+            let mut last_sent = self.last_sent.borrow_mut();
+            if last_sent.get(key).is_some_and(|at| at.elapsed() < repeat) {
+                return;
+            }
+            last_sent.insert(key.to_string(), Instant::now());
+        }
+        let mut hints = HashMap::new();
+        hints.insert("urgency".to_string(), (if critical { 2u8 } else { 1u8 }).to_variant());
+        hints.insert("desktop-entry".to_string(), "zelos".to_variant());
+        let params = ("zelos", 0u32, "zelos", summary, body, Vec::<String>::new(), hints, -1i32).to_variant();
+        connection.call(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            "org.freedesktop.Notifications",
+            "Notify",
+            Some(&params),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            None::<&gio::Cancellable>,
+            |result: Result<glib::Variant, glib::Error>| {
+                if let Err(e) = result {
+                    eprintln!("Failed to send a notification: {}", e);
+                }
+            },
+        );
+    }
+
+    /// Checks a sample against the temperature and power thresholds.
+    pub fn check(&self, sample: &GpuSample) {
+        let units = self.prefs.borrow().units;
+        let prefs = self.prefs.borrow().notifications.clone();
+        if !prefs.enabled {
+            return;
+        }
+        let power_pct = match (sample.power, sample.power_limit) {
+            (Some(cur), Some(limit)) if limit > 0 => Some(cur as f64 / limit as f64 * 100.0),
+            _ => None,
+        };
+        let conditions = [
+            (Sustained::Temperature, sample.temp.map(|t| t >= prefs.temp_c), prefs.temp_secs, tr!("GPU {} has been at {} or more for {} s", sample.index, units.temp(prefs.temp_c), prefs.temp_secs)),
+            (Sustained::Power, power_pct.map(|p| p >= prefs.power_pct as f64), prefs.power_secs, tr!("GPU {} has drawn {} % of its power limit or more for {} s", sample.index, prefs.power_pct, prefs.power_secs)),
+        ];
+        for (condition, holds, secs, body) in conditions {
+            let key = (sample.index, condition);
+            if holds != Some(true) {
+                self.over.borrow_mut().remove(&key);
+                continue;
+            }
+            let (since, reported) = *self.over.borrow_mut().entry(key).or_insert((sample.t, false));
+            if !reported && sample.t - since >= secs as f64 {
+                self.over.borrow_mut().insert(key, (since, true));
+                let summary = match condition {
+                    Sustained::Temperature => tr!("GPU {} is running hot", sample.index),
+                    Sustained::Power => tr!("GPU {} is at its power limit", sample.index),
+                };
+                self.send(&format!("{:?}-{}", condition, sample.index), &summary, &body, false);
+            }
+        }
+    }
+
+    pub fn xid(&self, index: u32, detail: &str) {
+        let prefs = self.prefs.borrow().notifications.clone();
+        if prefs.enabled && prefs.xid {
+            self.send(&format!("xid-{}-{}", index, detail), &tr!("GPU {} reported an Xid error", index), detail, true);
+        }
+    }
+
+    /// Reports a rejected setting or a profile that failed to apply.
+    pub fn failure(&self, summary: &str, body: &str) {
+        let prefs = self.prefs.borrow().notifications.clone();
+        if prefs.enabled && prefs.failures {
+            self.send(&format!("failure-{}", summary), summary, body, false);
+        }
+    }
+}
@@ -0,0 +1,411 @@
+// Per-user GUI preferences, kept apart from the system-wide zelos config
+
+use adw::prelude::*;
+use libadwaita as adw;
+use serde::{Deserialize, Serialize};
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use super::chart::Metric;
+use super::i18n::{number, tr};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PowerUnit {
+    #[default]
+    Watts,
+    Milliwatts,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TempUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+/// Units readouts and charts are shown in. Settings are still entered in W.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Units {
+    pub power: PowerUnit,
+    pub temp: TempUnit,
+}
+
+impl Units {
+    pub fn power_value(self, mw: f64) -> f64 {
+        match self.power {
+            PowerUnit::Watts => mw / 1000.0,
+            PowerUnit::Milliwatts => mw,
+        }
+    }
+
+    pub fn power_unit(self) -> &'static str {
+        match self.power {
+            PowerUnit::Watts => "W",
+            PowerUnit::Milliwatts => "mW",
+        }
+    }
+
+    pub fn temp_value(self, c: f64) -> f64 {
+        match self.temp {
+            TempUnit::Celsius => c,
+            TempUnit::Fahrenheit => c * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn temp_unit(self) -> &'static str {
+        match self.temp {
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F",
+        }
+    }
+
+    /// `mw` rounded to whole units in the locale's format, e.g. "180 W".
+    pub fn power(self, mw: u32) -> String {
+        format!("{} {}", number(self.power_value(mw as f64), 0), self.power_unit())
+    }
+
+    /// `mw` with two decimals in W, e.g. "180.25 W".
+    pub fn power_precise(self, mw: u32) -> String {
+        match self.power {
+            PowerUnit::Watts => format!("{} W", number(mw as f64 / 1000.0, 2)),
+            PowerUnit::Milliwatts => format!("{} mW", number(mw as f64, 0)),
+        }
+    }
+
+    pub fn temp(self, c: u32) -> String {
+        format!("{} {}", number(self.temp_value(c as f64), 0), self.temp_unit())
+    }
+}
+
+/// `#rrggbb` as RGB in 0..1.
+fn parse_color(hex: &str) -> Option<(f64, f64, f64)> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok().map(|c| c as f64 / 255.0);
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn format_color((r, g, b): (f64, f64, f64)) -> String {
+    let channel = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+/// When the GUI sends desktop notifications.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationPrefs {
+    pub enabled: bool,
+    /// Alert when a GPU stays at or above this temperature (°C)...
+    pub temp_c: u32,
+    /// ...for this many seconds
+    pub temp_secs: u32,
+    /// Alert when power draw stays at or above this share of the limit (%)...
+    pub power_pct: u32,
+    /// ...for this many seconds
+    pub power_secs: u32,
+    pub xid: bool,
+    /// Alert when NVML rejects a setting or a profile fails to apply
+    pub failures: bool,
+    /// Minimum time between two alerts of the same kind for the same GPU
+    pub repeat_mins: u32,
+}
+
+impl Default for NotificationPrefs {
+    fn default() -> Self {
+        Self { enabled: true, temp_c: 85, temp_secs: 10, power_pct: 100, power_secs: 30, xid: true, failures: true, repeat_mins: 10 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MiniMonitorPrefs {
+    /// The mini monitor was open when the GUI was last closed
+    pub open: bool,
+    /// Show the core clock history under the readouts
+    pub sparkline: bool,
+    /// Window opacity, from 0.3 to 1
+    pub opacity: f64,
+}
+
+impl Default for MiniMonitorPrefs {
+    fn default() -> Self {
+        Self { open: false, sparkline: true, opacity: 1.0 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Preferences {
+    /// Window width and height when it was last closed
+    pub window_size: Option<(i32, i32)>,
+    pub last_gpu: Option<u32>,
+    /// Name of the last visible tab
+    pub tab: Option<String>,
+    /// Seconds of history the charts show
+    pub chart_window_secs: f64,
+    pub sample_interval_ms: u64,
+    pub units: Units,
+    /// Chart line colors as `#rrggbb`, by metric id; missing ones use the
+    /// defaults
+    pub chart_colors: BTreeMap<String, String>,
+    /// Closing the window leaves the tray indicator running
+    pub keep_running: bool,
+    pub notifications: NotificationPrefs,
+    pub mini_monitor: MiniMonitorPrefs,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            window_size: None,
+            last_gpu: None,
+            tab: None,
+            chart_window_secs: 60.0,
+            sample_interval_ms: 1000,
+            units: Units::default(),
+            chart_colors: BTreeMap::new(),
+            keep_running: false,
+            notifications: NotificationPrefs::default(),
+            mini_monitor: MiniMonitorPrefs::default(),
+        }
+    }
+}
+
+impl Preferences {
+    /// Chart colors that replace the defaults.
+    pub fn chart_colors(&self) -> HashMap<Metric, (f64, f64, f64)> {
+        self.chart_colors.iter().filter_map(|(id, hex)| Some((Metric::from_id(id)?, parse_color(hex)?))).collect()
+    }
+
+    /// `$XDG_CONFIG_HOME/zelos/preferences.json`, falling back to `~/.config`.
+    pub fn path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("zelos").join("preferences.json"))
+    }
+
+    /// Loads the preferences, using the defaults for anything missing or
+    /// unreadable.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                eprintln!("Ignoring {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("neither XDG_CONFIG_HOME nor HOME is set")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| e.to_string())
+    }
+}
+
+fn switch_row(title: &str, subtitle: &str, active: bool, on_change: impl Fn(bool) + 'static) -> adw::ActionRow {
+    let row = adw::ActionRow::new();
+    row.set_title(title);
+    if !subtitle.is_empty() {
+        row.set_subtitle(subtitle);
+    }
+    let switch = gtk4::Switch::new();
+    switch.set_active(active);
+    switch.set_valign(gtk4::Align::Center);
+    switch.connect_active_notify(move |s| on_change(s.is_active()));
+    row.add_suffix(&switch);
+    row.set_activatable_widget(Some(&switch));
+    row
+}
+
+fn spin_row(title: &str, unit: &str, (min, max): (u32, u32), value: u32, on_change: impl Fn(u32) + 'static) -> adw::ActionRow {
+    let row = adw::ActionRow::new();
+    row.set_title(title);
+    let spin = gtk4::SpinButton::with_range(min as f64, max as f64, 1.0);
+    spin.set_value(value as f64);
+    spin.set_valign(gtk4::Align::Center);
+    spin.connect_value_changed(move |s| on_change(s.value() as u32));
+    row.add_suffix(&spin);
+    row.add_suffix(&gtk4::Label::new(Some(unit)));
+    row
+}
+
+fn combo_row(title: &str, options: &[&str], active: u32, on_change: impl Fn(u32) + 'static) -> adw::ActionRow {
+    let row = adw::ActionRow::new();
+    row.set_title(title);
+    let combo = gtk4::ComboBoxText::new();
+    for option in options {
+        combo.append_text(option);
+    }
+    combo.set_active(Some(active));
+    combo.set_valign(gtk4::Align::Center);
+    combo.connect_changed(move |c| {
+        if let Some(i) = c.active() {
+            on_change(i);
+        }
+    });
+    row.add_suffix(&combo);
+    row
+}
+
+/// Opens the Preferences window. Changes take effect right away, calling
+/// `on_change`, and are saved when it's closed.
+pub fn show_window(parent: &impl IsA<gtk4::Window>, prefs: Rc<RefCell<Preferences>>, on_change: Rc<dyn Fn()>) {
+    let window = adw::PreferencesWindow::new();
+    window.set_transient_for(Some(parent));
+    window.set_modal(true);
+    window.set_search_enabled(false);
+
+    // --- General ---
+    let general_page = adw::PreferencesPage::new();
+    general_page.set_title(&tr!("General"));
+    general_page.set_icon_name(Some("preferences-system-symbolic"));
+
+    let units = prefs.borrow().units;
+    let units_group = adw::PreferencesGroup::new();
+    units_group.set_title(&tr!("Units"));
+    units_group.set_description(Some(&tr!("Used by readouts and charts; settings are still entered in W.")));
+    {
+        let prefs = prefs.clone();
+        let on_change = on_change.clone();
+        units_group.add(&combo_row(&tr!("Power"), &[tr!("Watts (W)").as_str(), tr!("Milliwatts (mW)").as_str()], (units.power == PowerUnit::Milliwatts) as u32, move |i| {
+            prefs.borrow_mut().units.power = if i == 1 { PowerUnit::Milliwatts } else { PowerUnit::Watts };
+            on_change();
+        }));
+    }
+    {
+        let prefs = prefs.clone();
+        let on_change = on_change.clone();
+        units_group.add(&combo_row(&tr!("Temperature"), &[tr!("Celsius (°C)").as_str(), tr!("Fahrenheit (°F)").as_str()], (units.temp == TempUnit::Fahrenheit) as u32, move |i| {
+            prefs.borrow_mut().units.temp = if i == 1 { TempUnit::Fahrenheit } else { TempUnit::Celsius };
+            on_change();
+        }));
+    }
+    general_page.add(&units_group);
+
+    let tray_group = adw::PreferencesGroup::new();
+    tray_group.set_title(&tr!("Tray"));
+    {
+        let prefs = prefs.clone();
+        let on_change = on_change.clone();
+        let keep_running = prefs.borrow().keep_running;
+        tray_group.add(&switch_row(&tr!("Keep running when closed"), &tr!("Leave the tray indicator running after closing the window"), keep_running, move |on| {
+            prefs.borrow_mut().keep_running = on;
+            on_change();
+        }));
+    }
+    general_page.add(&tray_group);
+    window.add(&general_page);
+
+    // --- Charts ---
+    let charts_page = adw::PreferencesPage::new();
+    charts_page.set_title(&tr!("Charts"));
+    charts_page.set_icon_name(Some("utilities-system-monitor-symbolic"));
+    let colors_group = adw::PreferencesGroup::new();
+    colors_group.set_title(&tr!("Colors"));
+    let custom = prefs.borrow().chart_colors();
+    let mut buttons = Vec::new();
+    for metric in Metric::ALL {
+        let (r, g, b) = custom.get(&metric).copied().unwrap_or_else(|| metric.color());
+        let button = gtk4::ColorButton::with_rgba(&gtk4::gdk::RGBA::new(r as f32, g as f32, b as f32, 1.0));
+        button.set_use_alpha(false);
+        button.set_valign(gtk4::Align::Center);
+        {
+            let prefs = prefs.clone();
+            let on_change = on_change.clone();
+            button.connect_color_set(move |b| {
+                let c = b.rgba();
+                prefs.borrow_mut().chart_colors.insert(metric.id().to_string(), format_color((c.red() as f64, c.green() as f64, c.blue() as f64)));
+                on_change();
+            });
+        }
+        let row = adw::ActionRow::new();
+        row.set_title(&metric.title());
+        row.add_suffix(&button);
+        colors_group.add(&row);
+        buttons.push((metric, button));
+    }
+    let reset = gtk4::Button::with_label(&tr!("Reset"));
+    reset.add_css_class("flat");
+    {
+        let prefs = prefs.clone();
+        let on_change = on_change.clone();
+        reset.connect_clicked(move |_| {
+            prefs.borrow_mut().chart_colors.clear();
+            for (metric, button) in &buttons {
+                let (r, g, b) = metric.color();
+                button.set_rgba(&gtk4::gdk::RGBA::new(r as f32, g as f32, b as f32, 1.0));
+            }
+            on_change();
+        });
+    }
+    reset.set_valign(gtk4::Align::Center);
+    let reset_row = adw::ActionRow::new();
+    reset_row.set_title(&tr!("Default colors"));
+    reset_row.add_suffix(&reset);
+    colors_group.add(&reset_row);
+    charts_page.add(&colors_group);
+    window.add(&charts_page);
+
+    // --- Notifications ---
+    let page = adw::PreferencesPage::new();
+    page.set_title(&tr!("Notifications"));
+    page.set_icon_name(Some("preferences-system-notifications-symbolic"));
+
+    let n = prefs.borrow().notifications.clone();
+    // Each row edits one field of the notification preferences.
+    macro_rules! set {
+        ($field:ident) => {{
+            let prefs = prefs.clone();
+            move |v| prefs.borrow_mut().notifications.$field = v
+        }};
+    }
+
+    let general = adw::PreferencesGroup::new();
+    general.add(&switch_row(&tr!("Desktop notifications"), &tr!("Alert when a GPU crosses the thresholds below"), n.enabled, set!(enabled)));
+    general.add(&switch_row(&tr!("Xid errors"), &tr!("Alert when the driver reports a critical Xid error"), n.xid, set!(xid)));
+    general.add(&switch_row(&tr!("Failed changes"), &tr!("Alert when NVML rejects a setting or a profile fails to apply"), n.failures, set!(failures)));
+    page.add(&general);
+
+    let temperature = adw::PreferencesGroup::new();
+    temperature.set_title(&tr!("Temperature"));
+    temperature.add(&spin_row(&tr!("Alert at or above"), "°C", (40, 110), n.temp_c, set!(temp_c)));
+    temperature.add(&spin_row(&tr!("For at least"), &tr!("s"), (0, 600), n.temp_secs, set!(temp_secs)));
+    page.add(&temperature);
+
+    let power = adw::PreferencesGroup::new();
+    power.set_title(&tr!("Power"));
+    power.add(&spin_row(&tr!("Alert at or above"), &tr!("% of limit"), (50, 120), n.power_pct, set!(power_pct)));
+    power.add(&spin_row(&tr!("For at least"), &tr!("s"), (0, 600), n.power_secs, set!(power_secs)));
+    page.add(&power);
+
+    let rate = adw::PreferencesGroup::new();
+    rate.set_title(&tr!("Rate Limiting"));
+    rate.add(&spin_row(&tr!("Repeat an alert at most every"), &tr!("min"), (1, 240), n.repeat_mins, set!(repeat_mins)));
+    page.add(&rate);
+
+    window.add(&page);
+    window.connect_close_request(move |_| {
+        if let Err(e) = prefs.borrow().save() {
+            eprintln!("Failed to save preferences: {}", e);
+        }
+        gtk4::Inhibit(false)
+    });
+    window.present();
+}
@@ -0,0 +1,112 @@
+// Samples NVML off the GTK main loop
+
+use gtk4::glib;
+use nvml_wrapper::{
+    bitmasks::device::ThrottleReasons,
+    enum_wrappers::device::{Clock, TemperatureSensor},
+    Device, Nvml,
+};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Selectable sampling intervals, in milliseconds.
+pub const INTERVALS: [u64; 4] = [500, 1000, 2000, 5000];
+
+/// Configured power limit (mW) and core and memory clock offsets (MHz).
+pub type LiveSettings = (Option<u32>, Option<i32>, Option<i32>);
+
+/// One reading of a GPU. Anything NVML couldn't report is `None`.
+#[derive(Clone, Debug)]
+pub struct GpuSample {
+    pub index: u32,
+    /// Seconds since the chart start
+    pub t: f64,
+    /// Used and total VRAM in MiB
+    pub memory: Option<(u64, u64)>,
+    pub core_clock: Option<u32>,
+    pub mem_clock: Option<u32>,
+    /// Power draw in mW
+    pub power: Option<u32>,
+    /// Enforced power limit in mW
+    pub power_limit: Option<u32>,
+    /// Driver default power limit in mW
+    pub power_default: Option<u32>,
+    pub temp: Option<u32>,
+    pub utilization: Option<u32>,
+    /// Speed of the first fan that reports one, in percent
+    pub fan: Option<u32>,
+    pub fan_rpm: Option<u32>,
+    pub settings: LiveSettings,
+    pub throttle: Option<ThrottleReasons>,
+}
+
+impl GpuSample {
+    fn read(index: u32, dev: &Device, t: f64) -> Self {
+        Self {
+            index,
+            t,
+            memory: dev.memory_info().ok().map(|mi| (mi.used / 1024 / 1024, mi.total / 1024 / 1024)),
+            core_clock: dev.clock_info(Clock::Graphics).ok(),
+            mem_clock: dev.clock_info(Clock::Memory).ok(),
+            power: dev.power_usage().ok(),
+            power_limit: dev.enforced_power_limit().ok(),
+            power_default: dev.power_management_limit_default().ok(),
+            temp: dev.temperature(TemperatureSensor::Gpu).ok(),
+            utilization: dev.utilization_rates().ok().map(|u| u.gpu),
+            // Try multiple fan indices; use the first successful reading
+            fan: (0..4).find_map(|i| dev.fan_speed(i).ok()),
+            fan_rpm: (0..4).find_map(|i| dev.fan_speed_rpm(i).ok()),
+            settings: (dev.power_management_limit().ok(), dev.gpc_clock_vf_offset().ok(), dev.mem_clock_vf_offset().ok()),
+            throttle: dev.current_throttle_reasons().ok(),
+        }
+    }
+}
+
+/// Samples `indices` on a worker thread every `interval_ms` milliseconds,
+/// sending one batch per round. A hung NVML call only delays the samples,
+/// never the UI. Until NVML can be initialized, e.g. when the GUI starts
+/// before the driver is loaded, it's retried every interval; so is a handle
+/// that lost a device, as it goes stale across a driver reload. The worker
+/// stops once the receiver is dropped.
+pub fn spawn(indices: Vec<u32>, start: Instant, interval_ms: Arc<AtomicU64>) -> glib::Receiver<Vec<GpuSample>> {
+    let (tx, rx) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+    std::thread::spawn(move || {
+        let mut nvml: Option<Nvml> = None;
+        let mut reported = false;
+        loop {
+            if nvml.is_none() {
+                match Nvml::init() {
+                    Ok(handle) => nvml = Some(handle),
+                    Err(e) if !reported => {
+                        eprintln!("Failed to initialize NVML, retrying: {:?}", e);
+                        reported = true;
+                    }
+                    Err(_) => {}
+                }
+            }
+            if let Some(handle) = &nvml {
+                let mut lost = false;
+                let batch = indices
+                    .iter()
+                    .filter_map(|&index| match handle.device_by_index(index) {
+                        Ok(dev) => Some(GpuSample::read(index, &dev, start.elapsed().as_secs_f64())),
+                        Err(_) => {
+                            lost = true;
+                            None
+                        }
+                    })
+                    .collect();
+                if tx.send(batch).is_err() {
+                    return;
+                }
+                if lost {
+                    nvml = None;
+                }
+            }
+            std::thread::sleep(Duration::from_millis(interval_ms.load(Ordering::Relaxed)));
+        }
+    });
+    rx
+}
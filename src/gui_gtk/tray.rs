@@ -0,0 +1,404 @@
+// StatusNotifierItem tray indicator, over gio's D-Bus
+
+use gtk4::prelude::*;
+use gtk4::{cairo, gio, glib};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::i18n::number;
+use super::prefs::Units;
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+const ITEM_IFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_IFACE: &str = "com.canonical.dbusmenu";
+
+const INTROSPECTION: &str = r#"<node>
+  <interface name="org.kde.StatusNotifierItem">
+    <property name="Category" type="s" access="read"/>
+    <property name="Id" type="s" access="read"/>
+    <property name="Title" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="WindowId" type="i" access="read"/>
+    <property name="IconName" type="s" access="read"/>
+    <property name="IconPixmap" type="a(iiay)" access="read"/>
+    <property name="IconThemePath" type="s" access="read"/>
+    <property name="ToolTip" type="(sa(iiay)ss)" access="read"/>
+    <property name="ItemIsMenu" type="b" access="read"/>
+    <property name="Menu" type="o" access="read"/>
+    <property name="XAyatanaLabel" type="s" access="read"/>
+    <method name="Activate"><arg name="x" type="i" direction="in"/><arg name="y" type="i" direction="in"/></method>
+    <method name="SecondaryActivate"><arg name="x" type="i" direction="in"/><arg name="y" type="i" direction="in"/></method>
+    <method name="ContextMenu"><arg name="x" type="i" direction="in"/><arg name="y" type="i" direction="in"/></method>
+    <method name="Scroll"><arg name="delta" type="i" direction="in"/><arg name="orientation" type="s" direction="in"/></method>
+    <signal name="NewTitle"/>
+    <signal name="NewIcon"/>
+    <signal name="NewToolTip"/>
+    <signal name="NewStatus"><arg name="status" type="s"/></signal>
+    <signal name="XAyatanaNewLabel"><arg name="label" type="s"/><arg name="guide" type="s"/></signal>
+  </interface>
+  <interface name="com.canonical.dbusmenu">
+    <property name="Version" type="u" access="read"/>
+    <property name="TextDirection" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconThemePath" type="as" access="read"/>
+    <method name="GetLayout">
+      <arg name="parentId" type="i" direction="in"/>
+      <arg name="recursionDepth" type="i" direction="in"/>
+      <arg name="propertyNames" type="as" direction="in"/>
+      <arg name="revision" type="u" direction="out"/>
+      <arg name="layout" type="(ia{sv}av)" direction="out"/>
+    </method>
+    <method name="GetGroupProperties">
+      <arg name="ids" type="ai" direction="in"/>
+      <arg name="propertyNames" type="as" direction="in"/>
+      <arg name="properties" type="a(ia{sv})" direction="out"/>
+    </method>
+    <method name="GetProperty">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="name" type="s" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <method name="Event">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="eventId" type="s" direction="in"/>
+      <arg name="data" type="v" direction="in"/>
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+    <method name="EventGroup">
+      <arg name="events" type="a(isvu)" direction="in"/>
+      <arg name="idErrors" type="ai" direction="out"/>
+    </method>
+    <method name="AboutToShow">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="needUpdate" type="b" direction="out"/>
+    </method>
+    <method name="AboutToShowGroup">
+      <arg name="ids" type="ai" direction="in"/>
+      <arg name="updatesNeeded" type="ai" direction="out"/>
+      <arg name="idErrors" type="ai" direction="out"/>
+    </method>
+    <signal name="ItemsPropertiesUpdated">
+      <arg name="updatedProps" type="a(ia{sv})"/>
+      <arg name="removedProps" type="a(ias)"/>
+    </signal>
+    <signal name="LayoutUpdated">
+      <arg name="revision" type="u"/>
+      <arg name="parent" type="i"/>
+    </signal>
+  </interface>
+</node>"#;
+
+/// What a tray menu entry does when clicked.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrayAction {
+    Open,
+    /// Apply a saved profile to a GPU
+    ApplyProfile(u32, String),
+    /// Put a GPU back to the driver defaults
+    Reset(u32),
+    /// Toggle keeping the indicator running when the window is closed
+    KeepRunning(bool),
+    Quit,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ItemKind {
+    Normal,
+    Separator,
+    /// Radio item, with whether it's selected
+    Radio(bool),
+    /// Check item, with whether it's checked
+    Check(bool),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MenuItem {
+    pub label: String,
+    pub kind: ItemKind,
+    pub action: Option<TrayAction>,
+}
+
+impl MenuItem {
+    pub fn new(label: impl Into<String>, kind: ItemKind, action: Option<TrayAction>) -> Self {
+        Self { label: label.into(), kind, action }
+    }
+
+    pub fn separator() -> Self {
+        Self::new("", ItemKind::Separator, None)
+    }
+
+    fn properties(&self) -> HashMap<String, glib::Variant> {
+        let mut props = HashMap::new();
+        match self.kind {
+            ItemKind::Separator => {
+                props.insert("type".to_string(), "separator".to_variant());
+                return props;
+            }
+            ItemKind::Radio(on) => {
+                props.insert("toggle-type".to_string(), "radio".to_variant());
+                props.insert("toggle-state".to_string(), (on as i32).to_variant());
+            }
+            ItemKind::Check(on) => {
+                props.insert("toggle-type".to_string(), "checkmark".to_variant());
+                props.insert("toggle-state".to_string(), (on as i32).to_variant());
+            }
+            ItemKind::Normal => {}
+        }
+        props.insert("label".to_string(), self.label.to_variant());
+        props.insert("enabled".to_string(), self.action.is_some().to_variant());
+        props
+    }
+}
+
+/// What the indicator shows; read by the D-Bus handlers.
+#[derive(Default)]
+struct State {
+    /// One line per GPU
+    tooltip: String,
+    /// Short text shown next to the icon by hosts that support it
+    label: String,
+    /// ARGB32 icons in network byte order, as (width, height, data)
+    pixmaps: Vec<(i32, i32, Vec<u8>)>,
+    /// Entries of the menu; an entry's id is its position plus one
+    items: Vec<MenuItem>,
+    revision: u32,
+}
+
+/// A tray indicator showing GPU temperature and power, with a menu.
+pub struct Tray {
+    connection: gio::DBusConnection,
+    state: Arc<Mutex<State>>,
+}
+
+impl Tray {
+    /// Registers the indicator with the session's StatusNotifierWatcher.
+    /// Returns `None` if there's no session bus or no tray host.
+    pub fn new(on_action: impl Fn(TrayAction) + 'static) -> Option<Self> {
+        let connection = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>).ok()?;
+        let node = gio::DBusNodeInfo::for_xml(INTROSPECTION).ok()?;
+        let state = Arc::new(Mutex::new(State::default()));
+
+        // The handlers must be Send, so clicks reach the GTK side through a
+        // channel.
+        let (tx, rx) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        rx.attach(None, move |action| {
+            on_action(action);
+            glib::Continue(true)
+        });
+        let tx = Mutex::new(tx);
+
+        let item_state = state.clone();
+        let tx_item = Mutex::new(tx.lock().ok()?.clone());
+        connection
+            .register_object(
+                ITEM_PATH,
+                &node.lookup_interface(ITEM_IFACE)?,
+                move |_, _, _, _, method, _, invocation| {
+                    if method == "Activate" {
+                        if let Ok(tx) = tx_item.lock() {
+                            let _ = tx.send(TrayAction::Open);
+                        }
+                    }
+                    invocation.return_value(None);
+                },
+                move |_, _, _, _, property| item_property(&item_state, property),
+                |_, _, _, _, _, _| false,
+            )
+            .ok()?;
+
+        let menu_state = state.clone();
+        connection
+            .register_object(
+                MENU_PATH,
+                &node.lookup_interface(MENU_IFACE)?,
+                move |_, _, _, _, method, params, invocation| {
+                    let reply = menu_call(&menu_state, &tx, method, &params);
+                    invocation.return_value(reply.as_ref());
+                },
+                |_, _, _, _, property| match property {
+                    "Version" => 3u32.to_variant(),
+                    "TextDirection" => "ltr".to_variant(),
+                    "Status" => "normal".to_variant(),
+                    _ => Vec::<String>::new().to_variant(),
+                },
+                |_, _, _, _, _, _| false,
+            )
+            .ok()?;
+
+        let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+        connection
+            .call_sync(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                "org.freedesktop.DBus",
+                "RequestName",
+                Some(&(name.as_str(), 0u32).to_variant()),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+                None::<&gio::Cancellable>,
+            )
+            .ok()?;
+        connection
+            .call_sync(
+                Some("org.kde.StatusNotifierWatcher"),
+                "/StatusNotifierWatcher",
+                "org.kde.StatusNotifierWatcher",
+                "RegisterStatusNotifierItem",
+                Some(&(name.as_str(),).to_variant()),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+                None::<&gio::Cancellable>,
+            )
+            .ok()?;
+
+        Some(Self { connection, state })
+    }
+
+    /// Updates the tooltip and label, and draws `temp` (°C) into the icon in
+    /// the preferred unit.
+    pub fn set_status(&self, tooltip: &str, label: &str, temp: Option<u32>, units: Units) {
+        let text = temp.map(|t| number(units.temp_value(t as f64), 0)).unwrap_or_else(|| "–".to_string());
+        let pixmaps = [22, 32, 48].into_iter().filter_map(|size| render_icon(size, &text)).collect();
+        if let Ok(mut state) = self.state.lock() {
+            state.tooltip = tooltip.to_string();
+            state.label = label.to_string();
+            state.pixmaps = pixmaps;
+        }
+        for signal in ["NewToolTip", "NewIcon", "NewTitle"] {
+            let _ = self.connection.emit_signal(None, ITEM_PATH, ITEM_IFACE, signal, None);
+        }
+        let _ = self.connection.emit_signal(None, ITEM_PATH, ITEM_IFACE, "XAyatanaNewLabel", Some(&(label, "").to_variant()));
+    }
+
+    /// Replaces the menu, if it changed.
+    pub fn set_menu(&self, items: Vec<MenuItem>) {
+        let revision = {
+            let Ok(mut state) = self.state.lock() else {
+                return;
+            };
+            if state.items == items {
+                return;
+            }
+            state.items = items;
+            state.revision += 1;
+            state.revision
+        };
+        let _ = self.connection.emit_signal(None, MENU_PATH, MENU_IFACE, "LayoutUpdated", Some(&(revision, 0i32).to_variant()));
+    }
+}
+
+fn item_property(state: &Mutex<State>, property: &str) -> glib::Variant {
+    let Ok(state) = state.lock() else {
+        return "".to_variant();
+    };
+    match property {
+        "Category" => "Hardware".to_variant(),
+        "Id" => "zelos".to_variant(),
+        "Title" => format!("zelos: {}", state.label).to_variant(),
+        "Status" => "Active".to_variant(),
+        "WindowId" => 0i32.to_variant(),
+        "IconName" => if state.pixmaps.is_empty() { "video-display" } else { "" }.to_variant(),
+        "IconPixmap" => state.pixmaps.to_variant(),
+        "ToolTip" => ("video-display", Vec::<(i32, i32, Vec<u8>)>::new(), "zelos", state.tooltip.as_str()).to_variant(),
+        "ItemIsMenu" => false.to_variant(),
+        "Menu" => glib::variant::ObjectPath::try_from(MENU_PATH).map(|p| p.to_variant()).unwrap_or_else(|_| "".to_variant()),
+        "XAyatanaLabel" => state.label.to_variant(),
+        _ => "".to_variant(),
+    }
+}
+
+/// Answers a `com.canonical.dbusmenu` method call.
+fn menu_call(state: &Mutex<State>, tx: &Mutex<glib::Sender<TrayAction>>, method: &str, params: &glib::Variant) -> Option<glib::Variant> {
+    let state = state.lock().ok()?;
+    let click = |id: i32, event: &str| {
+        if event != "clicked" {
+            return;
+        }
+        let action = usize::try_from(id - 1).ok().and_then(|i| state.items.get(i)).and_then(|item| item.action.clone());
+        if let (Some(action), Ok(tx)) = (action, tx.lock()) {
+            let _ = tx.send(action);
+        }
+    };
+    match method {
+        "GetLayout" => {
+            let children: Vec<glib::Variant> = state
+                .items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| (i as i32 + 1, item.properties(), Vec::<glib::Variant>::new()).to_variant())
+                .collect();
+            let mut root = HashMap::new();
+            root.insert("children-display".to_string(), "submenu".to_variant());
+            Some((state.revision, (0i32, root, children)).to_variant())
+        }
+        "GetGroupProperties" => {
+            let (ids, _) = params.get::<(Vec<i32>, Vec<String>)>()?;
+            let props: Vec<(i32, HashMap<String, glib::Variant>)> = ids
+                .into_iter()
+                .filter_map(|id| Some((id, state.items.get(usize::try_from(id - 1).ok()?)?.properties())))
+                .collect();
+            Some((props,).to_variant())
+        }
+        "GetProperty" => {
+            let (id, name) = params.get::<(i32, String)>()?;
+            let value = state.items.get(usize::try_from(id - 1).ok()?)?.properties().remove(&name)?;
+            Some((value,).to_variant())
+        }
+        "Event" => {
+            let (id, event, _, _) = params.get::<(i32, String, glib::Variant, u32)>()?;
+            click(id, &event);
+            None
+        }
+        "EventGroup" => {
+            let (events,) = params.get::<(Vec<(i32, String, glib::Variant, u32)>,)>()?;
+            for (id, event, _, _) in events {
+                click(id, &event);
+            }
+            Some((Vec::<i32>::new(),).to_variant())
+        }
+        "AboutToShow" => Some((false,).to_variant()),
+        "AboutToShowGroup" => Some((Vec::<i32>::new(), Vec::<i32>::new()).to_variant()),
+        _ => None,
+    }
+}
+
+/// Draws `text`, the temperature, onto a square icon, as ARGB32 in network
+/// byte order.
+fn render_icon(size: i32, text: &str) -> Option<(i32, i32, Vec<u8>)> {
+    let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, size, size).ok()?;
+    {
+        let cr = cairo::Context::new(&surface).ok()?;
+        let s = size as f64;
+        let r = s * 0.2;
+        cr.new_sub_path();
+        cr.arc(s - r, r, r, -std::f64::consts::FRAC_PI_2, 0.0);
+        cr.arc(s - r, s - r, r, 0.0, std::f64::consts::FRAC_PI_2);
+        cr.arc(r, s - r, r, std::f64::consts::FRAC_PI_2, std::f64::consts::PI);
+        cr.arc(r, r, r, std::f64::consts::PI, 3.0 * std::f64::consts::FRAC_PI_2);
+        cr.close_path();
+        cr.set_source_rgb(0.95, 0.55, 0.20);
+        cr.fill().ok()?;
+
+        cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+        cr.set_font_size(if text.chars().count() > 2 { s * 0.45 } else { s * 0.6 });
+        let ext = cr.text_extents(text).ok()?;
+        cr.move_to((s - ext.width()) / 2.0 - ext.x_bearing(), (s - ext.height()) / 2.0 - ext.y_bearing());
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.show_text(text).ok()?;
+    }
+    surface.flush();
+    let stride = surface.stride() as usize;
+    let data = surface.data().ok()?;
+    // Cairo stores native-endian 32-bit ARGB; SNI wants big-endian.
+    let mut argb = Vec::with_capacity((size * size * 4) as usize);
+    for row in data.chunks(stride).take(size as usize) {
+        for px in row[..size as usize * 4].chunks_exact(4) {
+            argb.extend_from_slice(&u32::from_ne_bytes([px[0], px[1], px[2], px[3]]).to_be_bytes());
+        }
+    }
+    Some((size, size, argb))
+}
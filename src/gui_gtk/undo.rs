@@ -0,0 +1,189 @@
+// Settings applied from the GUI, per GPU, to step back and forth through
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use super::i18n::{number, tr};
+use crate::Sets;
+
+/// Older states are dropped past this many per GPU.
+const CAPACITY: usize = 50;
+
+#[derive(Clone, Debug)]
+pub struct AppliedState {
+    pub at: SystemTime,
+    pub sets: Sets,
+    /// What put the GPU in this state: "Applied", a profile, ...
+    pub label: String,
+}
+
+impl AppliedState {
+    /// Wall-clock time as HH:MM:SS UTC.
+    pub fn time(&self) -> String {
+        let time = humantime::format_rfc3339_seconds(self.at).to_string();
+        time.get(11..19).unwrap_or(&time).to_string()
+    }
+
+    /// The settings, briefly.
+    pub fn summary(&self) -> String {
+        let s = &self.sets;
+        let mut parts = Vec::new();
+        if let Some(p) = s.power_limit {
+            parts.push(tr!("{} W", number(p as f64 / 1000.0, 0)));
+        }
+        if let Some(f) = s.freq_offset {
+            parts.push(tr!("core {} MHz", format!("{:+}", f)));
+        }
+        if let Some(m) = s.mem_offset {
+            parts.push(tr!("memory {} MHz", format!("{:+}", m)));
+        }
+        if let (Some(min), Some(max)) = (s.min_clock, s.max_clock) {
+            parts.push(tr!("core {}–{} MHz", number(min as f64, 0), number(max as f64, 0)));
+        }
+        if let (Some(min), Some(max)) = (s.min_mem_clock, s.max_mem_clock) {
+            parts.push(tr!("memory {}–{} MHz", number(min as f64, 0), number(max as f64, 0)));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// The states of one GPU, oldest first, and which of them it's in.
+#[derive(Default)]
+struct Timeline {
+    states: Vec<AppliedState>,
+    current: usize,
+}
+
+/// Applied states of every GPU. Applying something new drops the states
+/// that were undone, like in an editor.
+#[derive(Default)]
+pub struct UndoHistory {
+    gpus: HashMap<u32, Timeline>,
+}
+
+impl UndoHistory {
+    /// Starts GPU `index`'s history with the state it was in before the
+    /// GUI first applied anything, unless it has one already.
+    pub fn ensure_baseline(&mut self, index: u32, sets: impl FnOnce() -> Sets) {
+        self.gpus.entry(index).or_insert_with(|| Timeline {
+            states: vec![AppliedState { at: SystemTime::now(), sets: sets(), label: tr!("Before changes") }],
+            current: 0,
+        });
+    }
+
+    /// Records `sets` as applied to GPU `index` and makes it the current state.
+    pub fn record(&mut self, index: u32, sets: Sets, label: String) {
+        let timeline = self.gpus.entry(index).or_default();
+        if !timeline.states.is_empty() {
+            timeline.states.truncate(timeline.current + 1);
+        }
+        timeline.states.push(AppliedState { at: SystemTime::now(), sets, label });
+        if timeline.states.len() > CAPACITY {
+            timeline.states.remove(0);
+        }
+        timeline.current = timeline.states.len() - 1;
+    }
+
+    /// GPU `index`'s states, oldest first, and the position of the current one.
+    pub fn states(&self, index: u32) -> (&[AppliedState], usize) {
+        self.gpus.get(&index).map(|t| (t.states.as_slice(), t.current)).unwrap_or((&[], 0))
+    }
+
+    pub fn get(&self, index: u32, position: usize) -> Option<&AppliedState> {
+        self.gpus.get(&index)?.states.get(position)
+    }
+
+    /// Position of the state before the current one.
+    pub fn previous(&self, index: u32) -> Option<usize> {
+        self.gpus.get(&index)?.current.checked_sub(1)
+    }
+
+    /// Position of the state that was undone last.
+    pub fn next(&self, index: u32) -> Option<usize> {
+        let timeline = self.gpus.get(&index)?;
+        (timeline.current + 1 < timeline.states.len()).then_some(timeline.current + 1)
+    }
+
+    /// Marks the state at `position` as the one GPU `index` is in, after
+    /// it was applied again.
+    pub fn set_current(&mut self, index: u32, position: usize) {
+        if let Some(timeline) = self.gpus.get_mut(&index).filter(|t| position < t.states.len()) {
+            timeline.current = position;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sets(power_limit: u32) -> Sets {
+        Sets { power_limit: Some(power_limit), ..Default::default() }
+    }
+
+    fn power_limits(history: &UndoHistory, index: u32) -> Vec<u32> {
+        history.states(index).0.iter().filter_map(|s| s.sets.power_limit).collect()
+    }
+
+    #[test]
+    fn baseline_is_taken_once() {
+        let mut history = UndoHistory::default();
+        history.ensure_baseline(0, || sets(100_000));
+        history.ensure_baseline(0, || panic!("baseline taken twice"));
+        assert_eq!(power_limits(&history, 0), [100_000]);
+        assert_eq!(history.states(0).1, 0);
+        assert!(history.previous(0).is_none());
+        assert!(history.next(0).is_none());
+        // Other GPUs have histories of their own.
+        assert!(history.states(1).0.is_empty());
+    }
+
+    #[test]
+    fn steps_back_and_forth() {
+        let mut history = UndoHistory::default();
+        history.ensure_baseline(0, || sets(100_000));
+        history.record(0, sets(150_000), "Applied".to_string());
+        history.record(0, sets(200_000), "Applied".to_string());
+        assert_eq!(history.states(0).1, 2);
+        assert_eq!(history.previous(0), Some(1));
+        assert!(history.next(0).is_none());
+
+        history.set_current(0, 1);
+        assert_eq!(history.previous(0), Some(0));
+        assert_eq!(history.next(0), Some(2));
+        history.set_current(0, 0);
+        assert!(history.previous(0).is_none());
+        assert_eq!(history.next(0), Some(1));
+
+        // Out of range positions are ignored.
+        history.set_current(0, 3);
+        assert_eq!(history.states(0).1, 0);
+        assert_eq!(history.get(0, 2).and_then(|s| s.sets.power_limit), Some(200_000));
+        assert!(history.get(0, 3).is_none());
+    }
+
+    #[test]
+    fn recording_after_a_revert_drops_the_redo_tail() {
+        let mut history = UndoHistory::default();
+        history.ensure_baseline(0, || sets(100_000));
+        history.record(0, sets(150_000), "Applied".to_string());
+        history.record(0, sets(200_000), "Applied".to_string());
+        history.set_current(0, 1);
+        history.record(0, sets(250_000), "Applied".to_string());
+        assert_eq!(power_limits(&history, 0), [100_000, 150_000, 250_000]);
+        assert_eq!(history.states(0).1, 2);
+        assert!(history.next(0).is_none());
+    }
+
+    #[test]
+    fn drops_the_oldest_past_capacity() {
+        let mut history = UndoHistory::default();
+        for i in 0..CAPACITY as u32 + 5 {
+            history.record(0, sets(i), "Applied".to_string());
+        }
+        let (states, current) = history.states(0);
+        assert_eq!(states.len(), CAPACITY);
+        assert_eq!(current, CAPACITY - 1);
+        assert_eq!(states[0].sets.power_limit, Some(5));
+    }
+}
@@ -0,0 +1,340 @@
+use std::io;
+use std::os::unix::fs::{symlink, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const NAME: &str = "zelos";
+
+/// Init systems zelos can install a boot-time apply job for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitSystem {
+    Systemd,
+    OpenRc,
+    Runit,
+}
+
+impl std::fmt::Display for InitSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            InitSystem::Systemd => "systemd",
+            InitSystem::OpenRc => "OpenRC",
+            InitSystem::Runit => "runit",
+        })
+    }
+}
+
+/// State of the boot job on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    /// No job is installed.
+    Missing,
+    /// A job is installed but runs a different command.
+    Outdated,
+    /// The job is up to date but won't run at boot.
+    Disabled,
+    /// The job is up to date and enabled.
+    Current,
+}
+
+/// A boot-time apply job for one init system, installed under `root`.
+///
+/// Everything except [`Init::start`] and [`Init::stop`] only touches files
+/// below `root`, so it can be pointed at a scratch directory.
+pub struct Init {
+    pub system: InitSystem,
+    root: PathBuf,
+}
+
+impl Init {
+    /// Detects the init system running on `root` from the runtime directories
+    /// each one creates at boot.
+    pub fn detect(root: impl Into<PathBuf>) -> Option<Self> {
+        let root = root.into();
+        let system = if root.join("run/systemd/system").is_dir() {
+            InitSystem::Systemd
+        } else if root.join("run/openrc").is_dir() {
+            InitSystem::OpenRc
+        } else if root.join("run/runit").is_dir() || root.join("etc/runit/runsvdir").is_dir() {
+            InitSystem::Runit
+        } else {
+            return None;
+        };
+        Some(Self { system, root })
+    }
+
+    /// Path of the job file (the service directory for runit), as seen on the
+    /// target system.
+    pub fn job_path(&self) -> &'static str {
+        match self.system {
+            InitSystem::Systemd => "/etc/systemd/system/zelos.service",
+            InitSystem::OpenRc => "/etc/init.d/zelos",
+            // Artix keeps its services under /etc/runit, Void under /etc/sv.
+            InitSystem::Runit if self.is_artix_runit() => "/etc/runit/sv/zelos",
+            InitSystem::Runit => "/etc/sv/zelos",
+        }
+    }
+
+    /// Symlink that makes the job run at boot, as seen on the target system.
+    fn enable_link(&self) -> &'static str {
+        match self.system {
+            InitSystem::Systemd => "/etc/systemd/system/multi-user.target.wants/zelos.service",
+            InitSystem::OpenRc => "/etc/runlevels/default/zelos",
+            InitSystem::Runit if self.is_artix_runit() => "/etc/runit/runsvdir/default/zelos",
+            InitSystem::Runit => "/var/service/zelos",
+        }
+    }
+
+    fn is_artix_runit(&self) -> bool {
+        self.root.join("etc/runit/runsvdir").is_dir()
+    }
+
+    fn under_root(&self, path: &str) -> PathBuf {
+        self.root.join(path.trim_start_matches('/'))
+    }
+
+    /// The file holding the job below `root`, and its contents.
+    fn job_file(&self, command: &[String]) -> (PathBuf, String) {
+        match self.system {
+            InitSystem::Systemd => (
+                self.under_root(self.job_path()),
+                format!(
+                    "[Unit]\nDescription=Zelos Overclocking Service\nAfter=network.target\n\n[Service]\nExecStart={}\nUser=root\n\n[Install]\nWantedBy=multi-user.target\n",
+                    systemd_quote(command)
+                ),
+            ),
+            InitSystem::OpenRc => (
+                self.under_root(self.job_path()),
+                format!(
+                    "#!/sbin/openrc-run\n\ndescription=\"Zelos Overclocking Service\"\n\ndepend() {{\n\tafter modules\n}}\n\nstart() {{\n\tebegin \"Applying zelos settings\"\n\t{}\n\teend $?\n}}\n",
+                    shell_quote(command)
+                ),
+            ),
+            // runsv restarts services that exit, so stay up after applying,
+            // even if that failed: every restart would count as another
+            // unclean boot.
+            InitSystem::Runit => (
+                self.under_root(self.job_path()).join("run"),
+                format!("#!/bin/sh\n{}\nexec tail -f /dev/null\n", shell_quote(command)),
+            ),
+        }
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.under_root(self.job_path()).exists()
+    }
+
+    pub fn status(&self, command: &[String]) -> JobStatus {
+        let (path, contents) = self.job_file(command);
+        match std::fs::read_to_string(path) {
+            Err(_) => JobStatus::Missing,
+            Ok(existing) if existing != contents => JobStatus::Outdated,
+            Ok(_) if !self.is_enabled() => JobStatus::Disabled,
+            Ok(_) => JobStatus::Current,
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        std::fs::read_link(self.under_root(self.enable_link()))
+            .map(|target| target == Path::new(self.job_path()))
+            .unwrap_or(false)
+    }
+
+    /// Writes the job running `command` and enables it, replacing any
+    /// previous job.
+    pub fn install(&self, command: &[String]) -> io::Result<()> {
+        let (path, contents) = self.job_file(command);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, contents)?;
+        let mode = if self.system == InitSystem::Systemd { 0o644 } else { 0o755 };
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))?;
+
+        if !self.is_enabled() {
+            let link = self.under_root(self.enable_link());
+            if let Some(dir) = link.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            if link.symlink_metadata().is_ok() {
+                std::fs::remove_file(&link)?;
+            }
+            symlink(self.job_path(), &link)?;
+        }
+        Ok(())
+    }
+
+    /// Disables and deletes the job. Does nothing if it isn't installed.
+    pub fn remove(&self) -> io::Result<()> {
+        let link = self.under_root(self.enable_link());
+        if link.symlink_metadata().is_ok() {
+            std::fs::remove_file(link)?;
+        }
+        let path = self.under_root(self.job_path());
+        if path.is_dir() {
+            std::fs::remove_dir_all(path)?;
+        } else if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the freshly installed or updated job on the live system.
+    pub fn start(&self) -> io::Result<()> {
+        match self.system {
+            InitSystem::Systemd => {
+                run(&["systemctl", "daemon-reload"])?;
+                run(&["systemctl", "restart", NAME])
+            }
+            InitSystem::OpenRc => run(&["rc-service", NAME, "restart"]),
+            // runsvdir picks up a new service on its own; this covers updates.
+            InitSystem::Runit => run(&["sv", "restart", NAME]).or(Ok(())),
+        }
+    }
+
+    /// Stops the job on the live system ahead of removing it.
+    pub fn stop(&self) -> io::Result<()> {
+        match self.system {
+            InitSystem::Systemd => run(&["systemctl", "stop", NAME]),
+            InitSystem::OpenRc => run(&["rc-service", NAME, "stop"]),
+            InitSystem::Runit => run(&["sv", "down", NAME]),
+        }
+    }
+}
+
+fn run(cmd: &[&str]) -> io::Result<()> {
+    let status = Command::new(cmd[0]).args(&cmd[1..]).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("`{}` failed with {}", cmd.join(" "), status)))
+    }
+}
+
+/// Quotes `args` for a systemd `ExecStart=` line.
+fn systemd_quote(args: &[String]) -> String {
+    args.iter()
+        .map(|a| {
+            if a.is_empty() || a.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\' || c == '\'') {
+                format!("\"{}\"", a.replace('\\', "\\\\").replace('"', "\\\""))
+            } else {
+                a.replace('%', "%%")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quotes `args` for a POSIX shell.
+pub fn shell_quote(args: &[String]) -> String {
+    args.iter()
+        .map(|a| {
+            if !a.is_empty() && a.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c)) {
+                a.clone()
+            } else {
+                format!("'{}'", a.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Vec<String> {
+        ["/opt/my tools/zelos", "--file", "/etc/zelos.json"].iter().map(|s| s.to_string()).collect()
+    }
+
+    fn root_with(dirs: &[&str]) -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        for dir in dirs {
+            std::fs::create_dir_all(root.path().join(dir)).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn detects_running_init() {
+        let systemd = root_with(&["run/systemd/system"]);
+        assert_eq!(Init::detect(systemd.path()).map(|i| i.system), Some(InitSystem::Systemd));
+        let openrc = root_with(&["run/openrc"]);
+        assert_eq!(Init::detect(openrc.path()).map(|i| i.system), Some(InitSystem::OpenRc));
+        let runit = root_with(&["run/runit"]);
+        assert_eq!(Init::detect(runit.path()).map(|i| i.system), Some(InitSystem::Runit));
+        let unknown = root_with(&["run/s6"]);
+        assert!(Init::detect(unknown.path()).is_none());
+    }
+
+    #[test]
+    fn install_update_remove_roundtrip() {
+        let root = root_with(&[]);
+        for system in [InitSystem::Systemd, InitSystem::OpenRc, InitSystem::Runit] {
+            let init = Init { system, root: root.path().into() };
+            let cmd = command();
+            assert_eq!(init.status(&cmd), JobStatus::Missing);
+
+            init.install(&cmd).unwrap();
+            assert_eq!(init.status(&cmd), JobStatus::Current, "{}", system);
+
+            let mut newer = cmd.clone();
+            newer.push("--verbose".to_string());
+            assert_eq!(init.status(&newer), JobStatus::Outdated);
+            init.install(&newer).unwrap();
+            assert_eq!(init.status(&newer), JobStatus::Current);
+
+            init.remove().unwrap();
+            assert_eq!(init.status(&newer), JobStatus::Missing);
+            assert!(init.under_root(init.enable_link()).symlink_metadata().is_err());
+            // Removing twice is fine.
+            init.remove().unwrap();
+        }
+    }
+
+    #[test]
+    fn disabled_job_is_reported_and_reenabled() {
+        let root = root_with(&[]);
+        let init = Init { system: InitSystem::Systemd, root: root.path().into() };
+        init.install(&command()).unwrap();
+        std::fs::remove_file(init.under_root(init.enable_link())).unwrap();
+        assert_eq!(init.status(&command()), JobStatus::Disabled);
+        init.install(&command()).unwrap();
+        assert_eq!(init.status(&command()), JobStatus::Current);
+    }
+
+    #[test]
+    fn job_contents_quote_the_command() {
+        let root = root_with(&[]);
+        let init = Init { system: InitSystem::Systemd, root: root.path().into() };
+        init.install(&command()).unwrap();
+        let unit = std::fs::read_to_string(root.path().join("etc/systemd/system/zelos.service")).unwrap();
+        assert!(unit.contains("ExecStart=\"/opt/my tools/zelos\" --file /etc/zelos.json\n"));
+
+        let init = Init { system: InitSystem::OpenRc, root: root.path().into() };
+        init.install(&command()).unwrap();
+        let path = root.path().join("etc/init.d/zelos");
+        let script = std::fs::read_to_string(&path).unwrap();
+        assert!(script.starts_with("#!/sbin/openrc-run\n"));
+        assert!(script.contains("\t'/opt/my tools/zelos' --file /etc/zelos.json\n"));
+        assert_eq!(std::fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o755);
+    }
+
+    #[test]
+    fn runit_layouts() {
+        let void = root_with(&["run/runit"]);
+        let init = Init::detect(void.path()).unwrap();
+        init.install(&command()).unwrap();
+        let script = std::fs::read_to_string(void.path().join("etc/sv/zelos/run")).unwrap();
+        assert_eq!(script, "#!/bin/sh\n'/opt/my tools/zelos' --file /etc/zelos.json\nexec tail -f /dev/null\n");
+        assert_eq!(std::fs::read_link(void.path().join("var/service/zelos")).unwrap(), Path::new("/etc/sv/zelos"));
+
+        let artix = root_with(&["run/runit", "etc/runit/runsvdir/default"]);
+        let init = Init::detect(artix.path()).unwrap();
+        init.install(&command()).unwrap();
+        assert!(artix.path().join("etc/runit/sv/zelos/run").is_file());
+        assert_eq!(
+            std::fs::read_link(artix.path().join("etc/runit/runsvdir/default/zelos")).unwrap(),
+            Path::new("/etc/runit/sv/zelos")
+        );
+    }
+}
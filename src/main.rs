@@ -1,8 +1,19 @@
 use clap::{Args, CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Generator, Shell};
 use nvml_wrapper::{Device, Nvml};
-use serde::Deserialize;
-use std::{collections::HashMap, io};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::PathBuf,
+};
+mod apply;
+mod boot_guard;
+mod daemon;
+mod diff;
+mod fan;
+mod init;
+mod readiness;
 #[cfg(feature = "gui")]
 mod gui_gtk;
 
@@ -17,6 +28,9 @@ struct Cli {
     /// Launch the GTK4 GUI
     #[arg(long, default_value_t = false)]
     gui: bool,
+    /// Apply with boot-loop protection (used by the boot-time job)
+    #[arg(long, default_value_t = false)]
+    boot: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -29,6 +43,17 @@ enum Commands {
 
         #[command(flatten)]
         sets: Sets,
+
+        /// Apply every setting even if one fails and print the outcome of
+        /// each as JSON (used by the GUI)
+        #[arg(long, hide = true)]
+        json: bool,
+
+        /// Put every setting that isn't given back to the driver default
+        /// first, so the GPU ends up in exactly the given state (used by the
+        /// GUI to revert)
+        #[arg(long, hide = true, group = "Sets")]
+        reset_unset: bool,
     },
     /// Gets GPU parameters
     Get {
@@ -36,6 +61,51 @@ enum Commands {
         #[arg(short, long)]
         index: u32,
     },
+    /// Puts a GPU back to the driver defaults, leaving the config file alone
+    Reset {
+        /// GPU index
+        #[arg(short, long)]
+        index: u32,
+
+        /// Print the outcome of each setting as JSON (used by the GUI)
+        #[arg(long, hide = true)]
+        json: bool,
+    },
+    /// Shows where the live GPU state differs from the config file
+    Diff,
+    /// Reapplies the config file if the live GPU state no longer matches it
+    /// or it locks clocks
+    ///
+    /// Meant to be run after resume or a driver reload, which reset the
+    /// offsets and locked clocks.
+    Reapply,
+    /// Watches the live GPU state and warns about or enforces drift from the config file, and drives custom fan curves
+    Daemon,
+    /// Shows the drift history recorded by the daemon
+    Drift,
+    /// Replaces the config file with another one, after checking that it's valid
+    Save {
+        /// Config to install
+        source: PathBuf,
+        /// Also install or update the boot-time job that applies the config
+        #[arg(long)]
+        install_service: bool,
+    },
+    /// Lists or applies the named profiles saved in the config file
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Manages the boot-time apply job for the running init system
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+    /// Inspects or resets the boot-loop protection
+    BootGuard {
+        #[command(subcommand)]
+        action: BootGuardAction,
+    },
     /// Generate shell completion script
     Completion {
         /// The shell to generate the script for
@@ -44,34 +114,94 @@ enum Commands {
     },
 }
 
-#[derive(Args, Debug, Deserialize)]
+#[derive(Subcommand, Debug)]
+enum ServiceAction {
+    /// Installs or updates the job and starts it
+    Install {
+        /// Arguments the job passes to zelos; defaults to applying the config file
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Stops and removes the job
+    Remove,
+    /// Shows whether the job is installed and up to date
+    Status {
+        /// Arguments the job is expected to pass to zelos
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfileAction {
+    /// Lists the profiles of every GPU, marking the active ones
+    List,
+    /// Applies a profile and makes it the settings the config file applies
+    Apply {
+        /// Profile name
+        name: String,
+        /// GPU index
+        #[arg(short, long, default_value_t = 0)]
+        index: u32,
+        /// Apply every setting even if one fails and print the outcome of
+        /// each as JSON (used by the GUI)
+        #[arg(long, hide = true)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BootGuardAction {
+    /// Shows the unclean boot counter and whether defaults are being applied
+    Status,
+    /// Clears the counter so the next boot applies your settings again
+    Reset,
+    /// Clears the counter once the system has been up long enough
+    #[command(hide = true)]
+    Wait,
+}
+
+#[derive(Args, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[group(required = true, multiple = true)]
 struct Sets {
     /// GPU frequency offset
     #[arg(short, long, allow_hyphen_values = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     freq_offset: Option<i32>,
     /// GPU memory frequency offset
     #[arg(long, allow_hyphen_values = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     mem_offset: Option<i32>,
     /// GPU power limit in milliwatts
     #[arg(short, long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     power_limit: Option<u32>,
     /// GPU min clock
     #[arg(long, requires = "max_clock")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     min_clock: Option<u32>,
     /// GPU max clock
     #[arg(long, requires = "min_clock")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     max_clock: Option<u32>,
     /// GPU min memory clock
     #[arg(long, requires = "max_mem_clock")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     min_mem_clock: Option<u32>,
     /// GPU max memory clock
     #[arg(long, requires = "min_mem_clock")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     max_mem_clock: Option<u32>,
 }
 
 impl Sets {
+    /// Whether core or memory clocks are locked. NVML can't read locks back,
+    /// so they can't be checked for drift.
+    fn locks_clocks(&self) -> bool {
+        (self.min_clock.is_some() && self.max_clock.is_some()) || (self.min_mem_clock.is_some() && self.max_mem_clock.is_some())
+    }
+
     fn apply(&self, device: &mut Device) {
         if let Some(freq_offset) = self.freq_offset {
             device
@@ -111,9 +241,44 @@ impl Sets {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 struct Config {
     sets: HashMap<u32, Sets>,
+    /// Named settings per GPU that can be switched to
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    profiles: HashMap<u32, BTreeMap<String, Sets>>,
+    /// The profile each GPU's `sets` were last switched to
+    #[serde(default, rename = "activeProfiles", skip_serializing_if = "HashMap::is_empty")]
+    active_profiles: HashMap<u32, String>,
+    /// Fan curves per GPU, driven by `zelos daemon`
+    #[serde(default, rename = "fanCurves", skip_serializing_if = "HashMap::is_empty")]
+    fan_curves: HashMap<u32, fan::FanCurve>,
+    /// Sections read through `config_section` (daemon, bootGuard, ...), kept
+    /// as-is so saving the config from the GUI doesn't drop them
+    #[serde(flatten)]
+    sections: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Config {
+    fn load(path: &str) -> Self {
+        let Ok(config_file) = std::fs::read_to_string(path) else {
+            panic!("Configuration file not found and no valid arguments were provided. Run `zelos --help` for more information.");
+        };
+
+        serde_json::from_str(&config_file).expect("Invalid configuration file")
+    }
+
+    /// Replaces the config file at `path` in one step, so a crash mid-write
+    /// can't leave a truncated config behind for the boot job.
+    fn save(&self, path: &str) -> io::Result<()> {
+        let path = std::path::Path::new(path);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(tmp, path)
+    }
 }
 
 fn main() {
@@ -183,8 +348,14 @@ fn main() {
 
     let cli = Cli::parse();
 
+    // The boot job logs to the journal, which hardly anyone reads; say it
+    // wherever zelos is run by hand too.
+    if !cli.boot && !matches!(cli.command, Some(Commands::BootGuard { .. } | Commands::Completion { .. })) {
+        boot_guard::warn_if_tripped(&cli.file);
+    }
+
     match &cli.command {
-        Some(Commands::Set { index, sets }) => {
+        Some(Commands::Set { index, sets, json, reset_unset }) => {
             escalate_permissions().expect("Failed to escalate permissions");
 
             sudo2::escalate_if_needed()
@@ -192,12 +363,36 @@ fn main() {
                 .or_else(|_| sudo2::pkexec())
                 .expect("Failed to escalate privileges");
 
-            let nvml = Nvml::init().expect("Failed to initialize NVML");
+            let guard = cli.boot.then(|| boot_guard::BootGuardConfig::load(&cli.file));
+            let verdict = guard.as_ref().map(|guard| boot_guard::begin(guard, &boot_guard::kernel_cmdline())).unwrap_or(boot_guard::Verdict::Apply);
+            if verdict == boot_guard::Verdict::Skip {
+                return;
+            }
+
+            let nvml = wait_for_driver(&cli.file, &[*index]);
 
             let mut device = nvml.device_by_index(*index).expect("Failed to get GPU");
 
-            sets.apply(&mut device);
-            println!("Successfully set GPU parameters.");
+            if verdict == boot_guard::Verdict::Defaults {
+                boot_guard::reset_device(*index, &mut device);
+                return;
+            }
+
+            let mut outcomes = if *reset_unset { apply::reset_unset(sets, &mut device) } else { Vec::new() };
+            if *json {
+                outcomes.extend(apply::apply_each(sets, &mut device));
+                print_outcomes(&outcomes);
+            } else {
+                for outcome in outcomes.iter().filter(|o| o.error.is_some()) {
+                    eprintln!("{}", outcome);
+                }
+                sets.apply(&mut device);
+                println!("Successfully set GPU parameters.");
+            }
+
+            if let Some(guard) = guard {
+                boot_guard::finish(&guard, &cli.file);
+            }
         }
         Some(Commands::Get { index }) => {
             let nvml = Nvml::init().expect("Failed to initialize NVML");
@@ -221,23 +416,218 @@ fn main() {
                 Err(e) => eprintln!("Failed to get GPU power limit: {:?}", e),
             }
         }
+        Some(Commands::Reset { index, json }) => {
+            escalate_permissions().expect("Failed to escalate permissions");
+
+            let nvml = Nvml::init().expect("Failed to initialize NVML");
+            let mut device = nvml.device_by_index(*index).expect("Failed to get GPU");
+            if *json {
+                print_outcomes(&apply::reset_unset(&Sets::default(), &mut device));
+            } else {
+                boot_guard::reset_device(*index, &mut device);
+            }
+        }
+        Some(Commands::Diff) => {
+            let config = Config::load(&cli.file);
+            let nvml = Nvml::init().expect("Failed to initialize NVML");
+
+            for (index, sets) in config.sets {
+                let device = nvml.device_by_index(index).expect("Failed to get GPU");
+                let diffs = diff::diff(&sets, &device);
+                if diffs.is_empty() {
+                    println!("GPU {}: matches the config", index);
+                }
+                for d in diffs {
+                    println!("GPU {}: {}", index, d);
+                }
+            }
+        }
+        Some(Commands::Reapply) => {
+            // The boot job applies defaults while the guard is tripped, don't
+            // undo that here.
+            let guard = boot_guard::BootGuardConfig::load(&cli.file);
+            match boot_guard::check(&guard, &boot_guard::kernel_cmdline()) {
+                boot_guard::Verdict::Apply => {}
+                boot_guard::Verdict::Defaults => {
+                    println!("Boot guard tripped, not reapplying settings.");
+                    return;
+                }
+                boot_guard::Verdict::Skip => {
+                    println!("{} is set on the kernel command line, not reapplying settings.", boot_guard::SAFE_FLAG);
+                    return;
+                }
+            }
+            let config = Config::load(&cli.file);
+
+            escalate_permissions().expect("Failed to escalate permissions");
+
+            let indices: Vec<u32> = config.sets.keys().copied().collect();
+            let nvml = wait_for_driver(&cli.file, &indices);
+
+            for (index, sets) in config.sets {
+                let mut device = nvml.device_by_index(index).expect("Failed to get GPU");
+                let diffs = diff::diff(&sets, &device);
+                // Locks can't be read back, so they're applied every time.
+                if diffs.is_empty() && !sets.locks_clocks() {
+                    println!("GPU {}: settings intact, nothing to reapply", index);
+                    continue;
+                }
+                for d in &diffs {
+                    println!("GPU {}: {}", index, d);
+                }
+                sets.apply(&mut device);
+                println!("GPU {}: reapplied settings", index);
+            }
+        }
+        Some(Commands::Daemon) => {
+            escalate_permissions().expect("Failed to escalate permissions");
+
+            daemon::run(&cli.file);
+        }
+        Some(Commands::Drift) => {
+            let daemon: daemon::DaemonConfig = config_section(&cli.file, "daemon");
+            let history = daemon::load_history(&daemon.history_file);
+            if history.is_empty() {
+                println!("No drift recorded.");
+            }
+            for event in history {
+                println!("{}", event);
+            }
+        }
+        Some(Commands::BootGuard { action }) => {
+            let guard = boot_guard::BootGuardConfig::load(&cli.file);
+            match action {
+                BootGuardAction::Status => {
+                    let state = boot_guard::BootGuardState::load(&guard.state_file);
+                    match state.tripped_message() {
+                        Some(message) => println!("{}", message),
+                        None => println!(
+                            "{} of {} unclean boots before defaults are applied.",
+                            state.unclean_boots, guard.max_unclean_boots
+                        ),
+                    }
+                }
+                BootGuardAction::Reset => {
+                    escalate_permissions().expect("Failed to escalate permissions");
+
+                    boot_guard::BootGuardState::default()
+                        .save(&guard.state_file)
+                        .expect("Failed to save boot guard state");
+                    println!("Boot guard reset, your settings will be applied at the next boot.");
+                }
+                BootGuardAction::Wait => boot_guard::wait_until_stable(&guard),
+            }
+        }
         None => {
-                let Ok(config_file) = std::fs::read_to_string(cli.file) else {
-                panic!("Configuration file not found and no valid arguments were provided. Run `zelos --help` for more information.");
-            };
+            let config = Config::load(&cli.file);
 
             escalate_permissions().expect("Failed to escalate permissions");
 
-            let config: Config =
-                serde_json::from_str(&config_file).expect("Invalid configuration file");
+            let guard = cli.boot.then(|| boot_guard::BootGuardConfig::load(&cli.file));
+            let verdict = guard.as_ref().map(|guard| boot_guard::begin(guard, &boot_guard::kernel_cmdline())).unwrap_or(boot_guard::Verdict::Apply);
+            if verdict == boot_guard::Verdict::Skip {
+                return;
+            }
 
-            let nvml = Nvml::init().expect("Failed to initialize NVML");
+            let indices: Vec<u32> = config.sets.keys().copied().collect();
+            let nvml = wait_for_driver(&cli.file, &indices);
 
             for (index, sets) in config.sets {
                 let mut device = nvml.device_by_index(index).expect("Failed to get GPU");
-                sets.apply(&mut device);
+                if verdict == boot_guard::Verdict::Defaults {
+                    boot_guard::reset_device(index, &mut device);
+                } else {
+                    sets.apply(&mut device);
+                }
+            }
+            if verdict == boot_guard::Verdict::Apply {
+                println!("Successfully set GPU parameters.");
+            }
+
+            if let Some(guard) = guard.filter(|_| verdict == boot_guard::Verdict::Apply) {
+                boot_guard::finish(&guard, &cli.file);
+            }
+        }
+        Some(Commands::Save { source, install_service: service }) => {
+            let contents = std::fs::read_to_string(source).expect("Failed to read the new config");
+            let config: Config = serde_json::from_str(&contents).expect("Invalid configuration file");
+
+            escalate_permissions().expect("Failed to escalate permissions");
+
+            config.save(&cli.file).expect("Failed to save the config file");
+            println!("Saved {}.", cli.file);
+
+            if *service {
+                install_service(&detect_init(), &boot_job_command(&cli.file, &[]));
+            }
+        }
+        Some(Commands::Profile { action }) => match action {
+            ProfileAction::List => {
+                let config = Config::load(&cli.file);
+                let mut indices: Vec<&u32> = config.profiles.keys().collect();
+                indices.sort();
+                for index in indices {
+                    println!("GPU {}:", index);
+                    let active = config.active_profiles.get(index);
+                    for name in config.profiles[index].keys() {
+                        let marker = if active == Some(name) { '*' } else { ' ' };
+                        println!("  {} {}", marker, name);
+                    }
+                }
+            }
+            ProfileAction::Apply { name, index, json } => {
+                let mut config = Config::load(&cli.file);
+                let Some(sets) = config.profiles.get(index).and_then(|p| p.get(name)).cloned() else {
+                    eprintln!("GPU {} has no profile named {:?}.", index, name);
+                    std::process::exit(1);
+                };
+
+                escalate_permissions().expect("Failed to escalate permissions");
+
+                let nvml = wait_for_driver(&cli.file, &[*index]);
+                let mut device = nvml.device_by_index(*index).expect("Failed to get GPU");
+                if *json {
+                    // Exits before saving if any setting failed.
+                    print_outcomes(&apply::apply_each(&sets, &mut device));
+                } else {
+                    sets.apply(&mut device);
+                }
+
+                config.sets.insert(*index, sets);
+                config.active_profiles.insert(*index, name.clone());
+                config.save(&cli.file).expect("Failed to save the config file");
+                println!("Applied profile {:?} to GPU {}.", name, index);
+            }
+        },
+        Some(Commands::Service { action }) => {
+            let init = detect_init();
+            let job_command = |args: &[String]| boot_job_command(&cli.file, args);
+
+            match action {
+                ServiceAction::Install { args } => {
+                    escalate_permissions().expect("Failed to escalate permissions");
+
+                    install_service(&init, &job_command(args));
+                }
+                ServiceAction::Remove => {
+                    escalate_permissions().expect("Failed to escalate permissions");
+
+                    if let Err(e) = init.stop() {
+                        eprintln!("Failed to stop the boot job: {}", e);
+                    }
+                    init.remove().expect("Failed to remove the boot job");
+                    println!("Removed {}.", init.job_path());
+                }
+                ServiceAction::Status { args } => {
+                    let status = match init.status(&job_command(args)) {
+                        init::JobStatus::Missing => "not installed",
+                        init::JobStatus::Outdated => "installed, but runs a different command",
+                        init::JobStatus::Disabled => "installed, but not enabled at boot",
+                        init::JobStatus::Current => "installed and enabled",
+                    };
+                    println!("{} ({}): {}", init.job_path(), init.system, status);
+                }
             }
-            println!("Successfully set GPU parameters.");
         }
         Some(Commands::Completion { shell }) => {
             generate_completion_script(*shell);
@@ -245,6 +635,63 @@ fn main() {
     }
 }
 
+fn detect_init() -> init::Init {
+    init::Init::detect("/").expect("Unsupported init system: only systemd, OpenRC and runit are supported")
+}
+
+/// Command line of the boot job: apply `config_path`, or run `args` if given.
+fn boot_job_command(config_path: &str, args: &[String]) -> Vec<String> {
+    let exe = std::env::current_exe().expect("cannot get exe path").display().to_string();
+    let mut command = vec![exe, "--boot".to_string(), "--file".to_string(), config_path.to_string()];
+    command.extend(args.iter().cloned());
+    command
+}
+
+fn install_service(init: &init::Init, command: &[String]) {
+    let verb = if init.is_installed() { "Updated" } else { "Installed" };
+    init.install(command).expect("Failed to install the boot job");
+    init.start().expect("Failed to start the boot job");
+    println!("{} {} for {}.", verb, init.job_path(), init.system);
+}
+
+/// Prints `outcomes` as one line of JSON for the GUI, exiting with a failure
+/// status if any setting failed.
+fn print_outcomes(outcomes: &[apply::Outcome]) {
+    println!("{}", serde_json::to_string(outcomes).expect("Failed to serialize outcomes"));
+    if outcomes.iter().any(|o| o.error.is_some()) {
+        std::process::exit(1);
+    }
+}
+
+/// Reads one section of the config file, falling back to its defaults if the
+/// file or the section is missing, or with an error if the section doesn't
+/// parse. This lets commands like `set` honor the tuning sections without a
+/// full config.
+fn config_section<T: DeserializeOwned + Default>(path: &str, key: &str) -> T {
+    let section = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|mut v| v.get_mut(key).map(serde_json::Value::take));
+    match section.map(serde_json::from_value) {
+        Some(Ok(section)) => section,
+        Some(Err(e)) => {
+            eprintln!("Invalid \"{}\" section in {}, using the defaults: {}", key, path, e);
+            T::default()
+        }
+        None => T::default(),
+    }
+}
+
+/// Initializes NVML once the driver and the given GPUs are up, or exits with
+/// the reason they never were.
+fn wait_for_driver(config_path: &str, indices: &[u32]) -> Nvml {
+    let config: readiness::ReadinessConfig = config_section(config_path, "readiness");
+    readiness::wait(&config, indices).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
 fn escalate_permissions() -> Result<(), Box<dyn std::error::Error>> {
     if sudo2::running_as_root() {
         return Ok(());
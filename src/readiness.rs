@@ -0,0 +1,174 @@
+use nvml_wrapper::{error::NvmlError, Nvml};
+use serde::Deserialize;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReadinessConfig {
+    /// How long to keep retrying before giving up, in seconds
+    pub timeout_secs: u64,
+    /// Delay before the first retry; doubles after every attempt
+    pub initial_delay_ms: u64,
+    /// Upper bound for the delay between two attempts
+    pub max_delay_ms: u64,
+    /// Also wait for the GPUs to be in persistence mode, e.g. until
+    /// nvidia-persistenced is up
+    pub require_persistence_mode: bool,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            initial_delay_ms: 250,
+            max_delay_ms: 4000,
+            require_persistence_mode: false,
+        }
+    }
+}
+
+/// Why the driver wasn't ready.
+#[derive(Debug)]
+pub enum NotReady {
+    /// NVML couldn't be initialized: the nvidia module isn't loaded or the
+    /// library isn't installed.
+    DriverMissing(NvmlError),
+    /// The driver is up but the GPU with this index isn't there (yet).
+    DeviceMissing(u32, NvmlError),
+    /// The GPU with this index isn't in persistence mode.
+    PersistenceModeOff(u32),
+}
+
+impl fmt::Display for NotReady {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotReady::DriverMissing(e) => write!(f, "NVIDIA driver missing: failed to initialize NVML ({:?})", e),
+            NotReady::DeviceMissing(index, e) => write!(f, "GPU {} missing: the driver is loaded but the device isn't available ({:?})", index, e),
+            NotReady::PersistenceModeOff(index) => write!(f, "GPU {} is not in persistence mode", index),
+        }
+    }
+}
+
+impl NotReady {
+    /// Whether waiting can't help: a missing library won't show up.
+    fn is_permanent(&self) -> bool {
+        matches!(self, NotReady::DriverMissing(NvmlError::LibloadingError(_)))
+    }
+}
+
+fn check(config: &ReadinessConfig, indices: &[u32]) -> Result<Nvml, NotReady> {
+    let nvml = Nvml::init().map_err(NotReady::DriverMissing)?;
+    classify(config, indices, |index| nvml.device_by_index(index).map(|device| device.is_in_persistent_mode().unwrap_or(false)))?;
+    Ok(nvml)
+}
+
+/// Checks every GPU in `indices` once the driver is up. `device` opens a
+/// GPU and tells whether it's in persistence mode.
+fn classify(config: &ReadinessConfig, indices: &[u32], device: impl Fn(u32) -> Result<bool, NvmlError>) -> Result<(), NotReady> {
+    for &index in indices {
+        let persistent = device(index).map_err(|e| NotReady::DeviceMissing(index, e))?;
+        if config.require_persistence_mode && !persistent {
+            return Err(NotReady::PersistenceModeOff(index));
+        }
+    }
+    Ok(())
+}
+
+/// Delays between two attempts: the initial delay, doubling up to the
+/// maximum.
+fn delays(config: &ReadinessConfig) -> impl Iterator<Item = Duration> {
+    let max = Duration::from_millis(config.max_delay_ms);
+    std::iter::successors(Some(Duration::from_millis(config.initial_delay_ms.max(1))), move |&delay| Some((delay * 2).min(max)))
+}
+
+/// How long to wait before the next attempt, given the scheduled `delay`
+/// and the time `remaining` before the timeout; `None` once it's up.
+fn next_wait(delay: Duration, remaining: Duration) -> Option<Duration> {
+    (!remaining.is_zero()).then(|| delay.min(remaining))
+}
+
+/// Initializes NVML once the driver and every GPU in `indices` are ready,
+/// retrying with exponential backoff until the configured timeout.
+pub fn wait(config: &ReadinessConfig, indices: &[u32]) -> Result<Nvml, NotReady> {
+    let deadline = Instant::now() + Duration::from_secs(config.timeout_secs);
+    for delay in delays(config) {
+        let err = match check(config, indices) {
+            Ok(nvml) => return Ok(nvml),
+            Err(e) if e.is_permanent() => return Err(e),
+            Err(e) => e,
+        };
+
+        let Some(wait) = next_wait(delay, deadline.saturating_duration_since(Instant::now())) else {
+            return Err(err);
+        };
+        eprintln!("{}, retrying in {} ms", err, wait.as_millis());
+        std::thread::sleep(wait);
+    }
+    unreachable!("the delays never run out")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn delays_double_up_to_the_maximum() {
+        let config = ReadinessConfig { initial_delay_ms: 250, max_delay_ms: 1500, ..Default::default() };
+        let schedule: Vec<_> = delays(&config).take(6).collect();
+        assert_eq!(schedule, [ms(250), ms(500), ms(1000), ms(1500), ms(1500), ms(1500)]);
+
+        // A zero initial delay would never grow.
+        let config = ReadinessConfig { initial_delay_ms: 0, max_delay_ms: 4, ..Default::default() };
+        assert_eq!(delays(&config).take(4).collect::<Vec<_>>(), [ms(1), ms(2), ms(4), ms(4)]);
+    }
+
+    #[test]
+    fn waits_stop_at_the_timeout() {
+        assert_eq!(next_wait(ms(500), ms(2000)), Some(ms(500)));
+        assert_eq!(next_wait(ms(4000), ms(700)), Some(ms(700)));
+        assert_eq!(next_wait(ms(500), Duration::ZERO), None);
+
+        // Without time passing in the checks, the waits add up to the timeout.
+        let config = ReadinessConfig::default();
+        let mut remaining = Duration::from_secs(config.timeout_secs);
+        let mut total = Duration::ZERO;
+        for delay in delays(&config) {
+            let Some(wait) = next_wait(delay, remaining) else { break };
+            total += wait;
+            remaining -= wait;
+        }
+        assert_eq!(total, Duration::from_secs(config.timeout_secs));
+    }
+
+    #[test]
+    fn classifies_missing_devices_and_persistence_mode() {
+        let devices = |index: u32| match index {
+            0 => Ok(true),
+            1 => Ok(false),
+            _ => Err(NvmlError::NotFound),
+        };
+        let lenient = ReadinessConfig::default();
+        let strict = ReadinessConfig { require_persistence_mode: true, ..Default::default() };
+
+        assert!(classify(&lenient, &[0, 1], devices).is_ok());
+        assert!(matches!(classify(&lenient, &[0, 2], devices), Err(NotReady::DeviceMissing(2, NvmlError::NotFound))));
+        assert!(classify(&strict, &[0], devices).is_ok());
+        assert!(matches!(classify(&strict, &[0, 1], devices), Err(NotReady::PersistenceModeOff(1))));
+        assert!(matches!(classify(&strict, &[2, 1], devices), Err(NotReady::DeviceMissing(2, _))));
+    }
+
+    #[test]
+    fn only_a_missing_library_is_permanent() {
+        assert!(!NotReady::DriverMissing(NvmlError::DriverNotLoaded).is_permanent());
+        assert!(!NotReady::DeviceMissing(0, NvmlError::NotFound).is_permanent());
+        assert!(!NotReady::PersistenceModeOff(0).is_permanent());
+        if let Err(e @ NvmlError::LibloadingError(_)) = Nvml::init() {
+            assert!(NotReady::DriverMissing(e).is_permanent());
+        }
+    }
+}